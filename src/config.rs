@@ -1,118 +1,478 @@
-use anyhow::{Context, Result};
-use globset::{Glob, GlobSetBuilder};
-use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::cell::RefCell;
+use std::path::{Component, Path, PathBuf};
 use walkdir::WalkDir;
 
-const SUPPORTED_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+use crate::groups::ImportGroups;
+use crate::jsonc;
+use crate::vcs::IgnoreFiles;
+use crate::SqueezeOptions;
+
+/// Extensions processed by default: every JS/TS module extension Biome handles.
+pub const DEFAULT_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
 
 /// Directories that Biome ignores by default.
 const DEFAULT_IGNORE: &[&str] = &["node_modules", ".git"];
 
+/// Biome's default `files.maxSize`, in bytes.
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
 #[derive(Debug)]
 pub struct BiomeFiles {
     pub includes: Vec<String>,
     pub excludes: Vec<String>,
+    /// Whose glob semantics the include patterns follow.
+    pub version: BiomeVersion,
+    /// `assist.actions.source.organizeImports.options.groups` (Biome 2.x).
+    pub import_groups: ImportGroups,
+    /// Whether organizeImports is enabled outside of any override.
+    pub organize_imports: bool,
+    /// `overrides` entries that turn organizeImports on or off, in order.
+    pub overrides: Vec<OrganizeImportsOverride>,
+    /// Skip files ignored by `.gitignore` and friends (`vcs.useIgnoreFile` with
+    /// `vcs.enabled`).
+    pub use_ignore_file: bool,
+    /// `files.maxSize`: larger files are skipped.
+    pub max_size: u64,
+    /// Extensions of the files to select.
+    pub extensions: Extensions,
+    /// Settings the detected Biome version doesn't support, which were ignored.
+    pub warnings: Vec<String>,
+    /// Canonical paths of the files this was read from: the config itself and
+    /// everything it extends. Empty for a config parsed from a string.
+    pub sources: Vec<PathBuf>,
+}
+
+impl BiomeFiles {
+    /// How files selected by this config are squeezed.
+    pub fn squeeze_options(&self) -> SqueezeOptions {
+        SqueezeOptions {
+            groups: self.import_groups.clone(),
+            max_size: Some(self.max_size),
+            ..Default::default()
+        }
+    }
+}
+
+/// Changes applied on top of a loaded biome.json, from import-squeeze's own
+/// settings or the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Adjustments {
+    /// Added to biome.json's excludes.
+    pub excludes: Vec<String>,
+    /// Force `vcs.useIgnoreFile` on.
+    pub use_ignore_file: bool,
+    /// Replaces the default extensions.
+    pub extensions: Extensions,
+}
+
+impl Adjustments {
+    pub fn apply(&self, config: &mut BiomeFiles) {
+        config.excludes.extend(self.excludes.iter().cloned());
+        config.use_ignore_file |= self.use_ignore_file;
+        config.extensions = self.extensions.clone();
+    }
+}
+
+/// The file extensions import-squeeze processes, without the leading dot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extensions(Vec<String>);
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Extensions(DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect())
+    }
+}
+
+impl Extensions {
+    /// Add an extension (`mjs` or `.mjs`), or remove one with a `!` prefix
+    /// (`!cjs`).
+    pub fn update(&mut self, entry: &str) -> Result<()> {
+        let (remove, ext) = match entry.strip_prefix('!') {
+            Some(ext) => (true, ext),
+            None => (false, entry),
+        };
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        // `Path::extension` only ever sees the part after the last dot.
+        if ext.is_empty() || ext.contains(['.', '/', '\\']) {
+            bail!("Invalid extension \"{}\"", entry);
+        }
+        if remove {
+            self.0.retain(|e| e != ext);
+        } else if !self.0.iter().any(|e| e == ext) {
+            self.0.push(ext.to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether `path` has one of the extensions.
+    pub fn matches(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.0.iter().any(|e| e == ext))
+    }
+}
+
+/// An `overrides` entry that enables or disables organizeImports (directly, or
+/// by disabling the whole assist or linter) for the paths it matches.
+#[derive(Debug, Clone)]
+pub struct OrganizeImportsOverride {
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+    pub enabled: bool,
+}
+
+/// Biome major version, as far as file patterns are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BiomeVersion {
+    /// `files.include`/`files.ignore`: `*` also matches `/`, and a pattern that
+    /// matches a directory includes everything in it.
+    V1,
+    /// `files.includes`: `*` stops at `/`, and a pattern has to match the file
+    /// itself (`src/**`, not `src`).
+    V2,
 }
 
 /// Parse biome.json content and extract file patterns.
 /// Pure function — takes JSON string, returns config struct.
+/// Comments and trailing commas are allowed, as in Biome.
 pub fn parse_biome_config(content: &str) -> Result<BiomeFiles> {
-    let json: serde_json::Value =
-        serde_json::from_str(content).context("Failed to parse biome.json")?;
+    files_from_json(&jsonc::parse(content)?, None)
+}
+
+/// Extract file patterns from a parsed (and already merged) biome.json.
+///
+/// The Biome version comes from `$schema`, else from `installed` (the version
+/// found in `node_modules`). Patterns are then read the way that version reads
+/// them, and keys it doesn't support are reported in [`BiomeFiles::warnings`].
+/// When neither tells the version, it is guessed from the keys used, and the
+/// keys and patterns of both versions are accepted.
+fn files_from_json(
+    json: &serde_json::Value,
+    installed: Option<BiomeVersion>,
+) -> Result<BiomeFiles> {
+    let known = schema_version(json).or(installed);
+    let version = known.unwrap_or(if json.pointer("/files/includes").is_some() {
+        BiomeVersion::V2
+    } else {
+        BiomeVersion::V1
+    });
+    let mut warnings = unsupported_keys(json, known);
+
+    let (mut includes, mut excludes) =
+        read_patterns(json.get("files"), "files.", known, &mut warnings);
+    excludes.splice(0..0, DEFAULT_IGNORE.iter().map(|s| s.to_string()));
+    if includes.is_empty() {
+        includes.push("**".to_string());
+    }
+
+    let max_size = match json.pointer("/files/maxSize") {
+        Some(size) => size
+            .as_u64()
+            .with_context(|| format!("files.maxSize must be a number of bytes, not {}", size))?,
+        None => DEFAULT_MAX_SIZE,
+    };
+
+    let import_groups = match json.pointer("/assist/actions/source/organizeImports/options/groups")
+    {
+        Some(groups) => {
+            ImportGroups::from_json(groups).context("Invalid organizeImports groups")?
+        }
+        None => ImportGroups::default(),
+    };
+
+    let overrides = json
+        .get("overrides")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(i, entry)| override_from_json(entry, i, known, &mut warnings))
+        .collect();
+
+    Ok(BiomeFiles {
+        includes,
+        excludes,
+        version,
+        import_groups,
+        organize_imports: organize_imports_setting(json, known).unwrap_or(true),
+        overrides,
+        use_ignore_file: json.pointer("/vcs/enabled") == Some(&serde_json::Value::Bool(true))
+            && json.pointer("/vcs/useIgnoreFile") == Some(&serde_json::Value::Bool(true)),
+        max_size,
+        extensions: Extensions::default(),
+        warnings,
+        sources: Vec::new(),
+    })
+}
 
+/// Include and exclude patterns of `object` (`files` or an `overrides` entry).
+///
+/// Biome 2.x reads `includes`, where `!` negates a pattern and `!!` also keeps
+/// the matched files from being indexed; both exclude here. Biome 1.x reads
+/// `include` and `ignore`, and has no negation: a leading `!` is reported and
+/// the pattern skipped.
+fn read_patterns(
+    object: Option<&serde_json::Value>,
+    prefix: &str,
+    known: Option<BiomeVersion>,
+    warnings: &mut Vec<String>,
+) -> (Vec<String>, Vec<String>) {
     let mut includes = Vec::new();
-    let mut excludes: Vec<String> = DEFAULT_IGNORE.iter().map(|s| s.to_string()).collect();
-
-    if let Some(files) = json.get("files") {
-        // Biome supports both "include" and "includes"
-        let include_arr = files
-            .get("includes")
-            .or_else(|| files.get("include"))
-            .and_then(|v| v.as_array());
-        if let Some(include_arr) = include_arr {
-            for item in include_arr {
-                if let Some(s) = item.as_str() {
-                    if let Some(stripped) = s.strip_prefix('!') {
-                        excludes.push(stripped.to_string());
-                    } else {
-                        includes.push(s.to_string());
-                    }
-                }
+    let mut excludes = Vec::new();
+    let Some(object) = object else {
+        return (includes, excludes);
+    };
+    let strings = |key: &str| {
+        object
+            .get(key)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    let (key, patterns) = match known {
+        Some(BiomeVersion::V1) => ("include", strings("include")),
+        Some(BiomeVersion::V2) => ("includes", strings("includes")),
+        None if object.get("includes").is_some() => ("includes", strings("includes")),
+        None => ("include", strings("include")),
+    };
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(_) if known == Some(BiomeVersion::V1) => warnings.push(format!(
+                "{}{}: \"{}\" is not a negation in Biome 1.x and is ignored; use `ignore` instead",
+                prefix, key, pattern
+            )),
+            Some(negated) => {
+                let negated = negated.strip_prefix('!').unwrap_or(negated);
+                excludes.push(negated.to_string());
             }
+            None => includes.push(pattern),
         }
+    }
+    if known != Some(BiomeVersion::V2) {
+        excludes.extend(strings("ignore"));
+    }
+    (includes, excludes)
+}
 
-        // Biome uses "ignore" for exclude patterns
-        if let Some(ignore_arr) = files.get("ignore").and_then(|v| v.as_array()) {
-            for item in ignore_arr {
-                if let Some(s) = item.as_str() {
-                    excludes.push(s.to_string());
-                }
+/// Keys the detected Biome version doesn't support, as warnings. Nothing is
+/// reported when the version is unknown.
+fn unsupported_keys(json: &serde_json::Value, known: Option<BiomeVersion>) -> Vec<String> {
+    let (label, removed, removed_in_overrides): (_, &[&str], &[&str]) = match known {
+        Some(BiomeVersion::V1) => ("1.x", &["files.includes", "root"], &["includes"]),
+        Some(BiomeVersion::V2) => (
+            "2.x",
+            &["files.include", "files.ignore", "organizeImports"],
+            &["include", "ignore", "organizeImports"],
+        ),
+        None => return Vec::new(),
+    };
+
+    let mut keys: Vec<String> = removed
+        .iter()
+        .filter(|key| {
+            json.pointer(&format!("/{}", key.replace('.', "/")))
+                .is_some()
+        })
+        .map(|key| key.to_string())
+        .collect();
+    let overrides = json.get("overrides").and_then(|v| v.as_array());
+    for (i, entry) in overrides.into_iter().flatten().enumerate() {
+        for key in removed_in_overrides {
+            if entry.get(key).is_some() {
+                keys.push(format!("overrides[{}].{}", i, key));
             }
         }
     }
+    keys.into_iter()
+        .map(|key| {
+            format!(
+                "`{}` is not supported by Biome {} and is ignored",
+                key, label
+            )
+        })
+        .collect()
+}
+
+/// The Biome version a `$schema` URL names, as in
+/// `https://biomejs.dev/schemas/2.1.2/schema.json`.
+fn schema_version(json: &serde_json::Value) -> Option<BiomeVersion> {
+    let schema = json.get("$schema")?.as_str()?;
+    schema.split('/').find_map(parse_version)
+}
+
+/// The version of the `@biomejs/biome` package in the nearest `node_modules`
+/// above `dir`.
+fn installed_version(dir: &Path) -> Option<BiomeVersion> {
+    dir.ancestors().find_map(|dir| {
+        let manifest = dir.join("node_modules/@biomejs/biome/package.json");
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(manifest).ok()?).ok()?;
+        parse_version(json.get("version")?.as_str()?)
+    })
+}
+
+/// Major version of `1.9.4`, `2.0.0-beta.1` or `@biomejs/biome@2.1.0`.
+fn parse_version(version: &str) -> Option<BiomeVersion> {
+    let version = version.rsplit('@').next()?;
+    let mut parts = version.splitn(3, '.');
+    let major: u64 = parts.next()?.parse().ok()?;
+    parts.next()?.parse::<u64>().ok()?;
+    parts.next()?;
+    Some(if major >= 2 {
+        BiomeVersion::V2
+    } else {
+        BiomeVersion::V1
+    })
+}
+
+/// What a config object (the top level or an `overrides` entry) says about
+/// organizeImports: `organizeImports.enabled` in Biome 1.x, `assist.enabled` and
+/// the `assist.actions.source.organizeImports` level in Biome 2.x.
+fn organize_imports_setting(json: &serde_json::Value, known: Option<BiomeVersion>) -> Option<bool> {
+    let enabled = json
+        .pointer("/organizeImports/enabled")
+        .and_then(|v| v.as_bool());
+    if let (Some(enabled), false) = (enabled, known == Some(BiomeVersion::V2)) {
+        return Some(enabled);
+    }
+    let assist = json.pointer("/assist/enabled").and_then(|v| v.as_bool());
+    if assist == Some(false) {
+        return Some(false);
+    }
+    let action = json.pointer("/assist/actions/source/organizeImports");
+    let level = match action {
+        Some(serde_json::Value::Object(action)) => action.get("level"),
+        action => action,
+    };
+    match level.and_then(|v| v.as_str()) {
+        Some(level) => Some(level != "off"),
+        None => assist,
+    }
+}
 
+/// The `index`th `overrides` entry, if it changes whether organizeImports runs.
+fn override_from_json(
+    entry: &serde_json::Value,
+    index: usize,
+    known: Option<BiomeVersion>,
+    warnings: &mut Vec<String>,
+) -> Option<OrganizeImportsOverride> {
+    let linter_off = entry.pointer("/linter/enabled") == Some(&serde_json::Value::Bool(false));
+    let enabled = match organize_imports_setting(entry, known) {
+        Some(enabled) => enabled,
+        None if linter_off => false,
+        None => return None,
+    };
+
+    let prefix = format!("overrides[{}].", index);
+    let (mut includes, excludes) = read_patterns(Some(entry), &prefix, known, warnings);
     if includes.is_empty() {
         includes.push("**".to_string());
     }
 
-    Ok(BiomeFiles { includes, excludes })
+    Some(OrganizeImportsOverride {
+        includes,
+        excludes,
+        enabled,
+    })
 }
 
 /// Find biome.json by searching current dir then parent dirs.
+/// Nested package configs (`"root": false`) are skipped in favor of the root
+/// config above them, unless there is none.
 pub fn find_biome_config(start_dir: &Path) -> Option<PathBuf> {
-    let mut dir = start_dir.to_path_buf();
-    loop {
-        let config_path = dir.join("biome.json");
-        if config_path.exists() {
-            return Some(config_path);
-        }
-        let config_path = dir.join("biome.jsonc");
-        if config_path.exists() {
+    let mut nested = None;
+    for dir in start_dir.ancestors() {
+        let Some(config_path) = config_in(dir) else {
+            continue;
+        };
+        if !is_nested_config(&config_path) {
             return Some(config_path);
         }
-        if !dir.pop() {
-            return None;
-        }
+        nested.get_or_insert(config_path);
     }
+    nested
+}
+
+/// File names Biome looks for in each directory.
+pub(crate) const CONFIG_FILE_NAMES: &[&str] = &["biome.json", "biome.jsonc"];
+
+/// The biome.json (or biome.jsonc) directly in `dir`.
+fn config_in(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Whether the config at `path` is a nested one (`"root": false`).
+fn is_nested_config(path: &Path) -> bool {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|content| jsonc::parse(&content).ok())
+        .is_some_and(|json| json.get("root") == Some(&serde_json::Value::Bool(false)))
+}
+
+/// Matcher for the nested config in `dir`, if it has one.
+fn nested_matcher(dir: &Path) -> Result<Option<Matcher>> {
+    let Some(config_path) = config_in(dir).filter(|p| is_nested_config(p)) else {
+        return Ok(None);
+    };
+    let (config, base_dir) = load_biome_config(&config_path)?;
+    Ok(Some(Matcher::new(&config, &base_dir)?))
 }
 
 /// Resolve file paths by walking the directory tree.
 /// Skips excluded directories entirely (never enters node_modules, .git, etc).
 /// Only returns files with supported extensions that match include patterns.
+///
+/// Directories with a nested config (`"root": false`) use that config's include
+/// patterns for their subtree; excludes of every enclosing config still apply.
 pub fn resolve_file_paths(config: &BiomeFiles, base_dir: &Path) -> Result<Vec<PathBuf>> {
-    // Build include glob set
-    let mut include_builder = GlobSetBuilder::new();
-    for pattern in &config.includes {
-        for ext in SUPPORTED_EXTENSIONS {
-            let glob_pattern = if pattern.ends_with("**") {
-                format!("{}/*.{}", pattern, ext)
-            } else if pattern.ends_with('/') {
-                format!("{}**/*.{}", pattern, ext)
-            } else {
-                // Pattern already has an extension or is specific — use as-is
-                pattern.clone()
-            };
-            include_builder.add(
-                Glob::new(&glob_pattern)
-                    .with_context(|| format!("Invalid include pattern: {}", glob_pattern))?,
-            );
-        }
-    }
-    let include_set = include_builder
-        .build()
-        .context("Failed to build include glob set")?;
+    let root = Matcher::new(config, base_dir)?;
+    // Nested configs that apply to the current walk position, outermost first.
+    let nested = RefCell::new(Vec::new());
+    let nested_error = RefCell::new(None);
+    let ignore_files = config
+        .use_ignore_file
+        .then(|| RefCell::new(IgnoreFiles::new(base_dir)));
 
     let mut files = Vec::new();
-    let excludes = &config.excludes;
 
     let walker = WalkDir::new(base_dir)
         .follow_links(false)
         .into_iter()
         .filter_entry(|entry| {
+            let mut nested = nested.borrow_mut();
+            leave_scopes(&mut nested, entry.path());
+
             // Skip excluded directories entirely (don't descend into them)
             if entry.file_type().is_dir() {
-                let dir_name = entry.file_name().to_string_lossy();
-                return !excludes.iter().any(|ex| dir_name == *ex);
+                if scopes(&root, &nested).any(|m| m.is_excluded_dir(entry.path())) {
+                    return false;
+                }
+                if let Some(ignore_files) = &ignore_files {
+                    let mut ignore_files = ignore_files.borrow_mut();
+                    if ignore_files.is_ignored(entry.path(), true) {
+                        return false;
+                    }
+                    ignore_files.enter(entry.path());
+                }
+                if entry.depth() > 0 {
+                    match nested_matcher(entry.path()) {
+                        Ok(Some(matcher)) => nested.push(matcher),
+                        Ok(None) => {}
+                        Err(e) => {
+                            nested_error.borrow_mut().get_or_insert(e);
+                        }
+                    }
+                }
             }
             true
         });
@@ -129,17 +489,21 @@ pub fn resolve_file_paths(config: &BiomeFiles, base_dir: &Path) -> Result<Vec<Pa
         }
 
         let path = entry.path();
-
-        // Check supported extension
-        if !is_supported_file(path) {
+        if !is_included_by(&root, &nested.borrow(), path) {
             continue;
         }
-
-        // Check matches include pattern (relative to base_dir)
-        let rel_path = path.strip_prefix(base_dir).unwrap_or(path);
-        if include_set.is_match(rel_path) {
-            files.push(path.to_path_buf());
+        if let Some(ignore_files) = &ignore_files {
+            let mut ignore_files = ignore_files.borrow_mut();
+            ignore_files.enter(path.parent().unwrap_or(base_dir));
+            if ignore_files.is_ignored(path, false) {
+                continue;
+            }
         }
+        files.push(path.to_path_buf());
+    }
+
+    if let Some(e) = nested_error.into_inner() {
+        return Err(e);
     }
 
     files.sort();
@@ -147,11 +511,464 @@ pub fn resolve_file_paths(config: &BiomeFiles, base_dir: &Path) -> Result<Vec<Pa
     Ok(files)
 }
 
-fn is_supported_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext))
-        .unwrap_or(false)
+/// Drop nested configs whose directory doesn't contain `path`.
+fn leave_scopes(nested: &mut Vec<Matcher>, path: &Path) {
+    while nested.last().is_some_and(|m| m.relative(path).is_none()) {
+        nested.pop();
+    }
+}
+
+/// The root config followed by the nested ones below it.
+fn scopes<'a>(root: &'a Matcher, nested: &'a [Matcher]) -> impl Iterator<Item = &'a Matcher> {
+    std::iter::once(root).chain(nested)
+}
+
+/// A file is included if no enclosing config excludes it and the innermost one
+/// includes it and has organizeImports enabled for it.
+fn is_included_by(root: &Matcher, nested: &[Matcher], path: &Path) -> bool {
+    let innermost = nested.last().unwrap_or(root);
+    root.extensions.matches(path)
+        && scopes(root, nested).all(|m| !m.is_excluded(path))
+        && innermost.matches_includes(path)
+        && innermost.organize_imports_enabled(path)
+}
+
+/// Check whether a single path would be selected by [`resolve_file_paths`],
+/// without walking the tree. Used for stdin input and for paths reported by `--watch`.
+/// Relative paths are resolved against the current directory.
+pub fn is_included(config: &BiomeFiles, base_dir: &Path, path: &Path) -> Result<bool> {
+    is_included_with_nested(&Matcher::new(config, base_dir)?, path)
+}
+
+/// Like [`is_included`], starting from an already compiled root config. Nested
+/// configs between its directory and `path` are picked up on the way.
+pub fn is_included_with_nested(root: &Matcher, path: &Path) -> Result<bool> {
+    let Some(rel) = root.relative(path) else {
+        return Ok(false);
+    };
+    let mut dir = root.base_dir().to_path_buf();
+    let mut nested = Vec::new();
+    let mut ignore_files = root.ignore_files();
+    for component in rel.parent().into_iter().flat_map(|p| p.components()) {
+        dir.push(component);
+        if scopes(root, &nested).any(|m| m.is_excluded_dir(&dir)) {
+            return Ok(false);
+        }
+        if let Some(ignore_files) = &mut ignore_files {
+            ignore_files.enter(&dir);
+        }
+        nested.extend(nested_matcher(&dir)?);
+    }
+    let path = root.base_dir().join(rel);
+    let ignored = ignore_files.is_some_and(|i| i.is_ignored(&path, false));
+    Ok(!ignored && is_included_by(root, &nested, &path))
+}
+
+/// Whether Biome would organize the imports of `path`, according to the
+/// innermost config (`root` or a nested one) containing it. Include and exclude
+/// patterns are not considered, so this also applies to explicitly passed files.
+/// Paths outside the config's directory are always enabled.
+pub fn is_organize_imports_enabled(root: &Matcher, path: &Path) -> Result<bool> {
+    let Some(rel) = root.relative(path) else {
+        return Ok(true);
+    };
+    let mut dir = root.base_dir().to_path_buf();
+    let mut innermost = None;
+    for component in rel.parent().into_iter().flat_map(|p| p.components()) {
+        dir.push(component);
+        if let Some(matcher) = nested_matcher(&dir)? {
+            innermost = Some(matcher);
+        }
+    }
+    Ok(innermost
+        .as_ref()
+        .unwrap_or(root)
+        .organize_imports_enabled(path))
+}
+
+/// Squeeze options for `path` from the innermost nested config (`"root":
+/// false`) between `root`'s directory and the file, read with everything it
+/// extends (e.g. the root config, with `"extends": "//"`). `None` if no nested
+/// config applies, so the root config's options do.
+pub fn nested_squeeze_options(root: &Matcher, path: &Path) -> Result<Option<SqueezeOptions>> {
+    let Some(rel) = root.relative(path) else {
+        return Ok(None);
+    };
+    let mut dir = root.base_dir().to_path_buf();
+    let mut innermost = None;
+    for component in rel.parent().into_iter().flat_map(|p| p.components()) {
+        dir.push(component);
+        if let Some(config_path) = config_in(&dir).filter(|p| is_nested_config(p)) {
+            innermost = Some(config_path);
+        }
+    }
+    innermost
+        .map(|config_path| Ok(load_biome_config(&config_path)?.0.squeeze_options()))
+        .transpose()
+}
+
+/// Compiled include/exclude patterns of a [`BiomeFiles`]. Patterns are matched
+/// against paths relative to the config's directory; relative paths passed in
+/// are resolved against the current directory.
+///
+/// Include and exclude patterns follow the semantics of the config's
+/// [`BiomeVersion`]. An exclude pattern excludes every path it matches and
+/// everything below it. In Biome 1.x, exclude patterns without a `/` (like
+/// `node_modules` or `*.test.ts`) match at any depth, as in `.gitignore`; in
+/// 2.x they are relative to the config directory like any other pattern.
+pub struct Matcher {
+    cwd: PathBuf,
+    base_dir: PathBuf,
+    version: BiomeVersion,
+    includes: GlobSet,
+    excludes: GlobSet,
+    /// Directories that can be skipped without looking inside: those matched by
+    /// an exclude pattern, and `dir` for a pattern `dir/**`.
+    excluded_dirs: GlobSet,
+    organize_imports: bool,
+    overrides: Vec<CompiledOverride>,
+    use_ignore_file: bool,
+    extensions: Extensions,
+}
+
+struct CompiledOverride {
+    includes: GlobSet,
+    excludes: GlobSet,
+    enabled: bool,
+}
+
+impl Matcher {
+    pub fn new(config: &BiomeFiles, base_dir: &Path) -> Result<Self> {
+        let cwd = std::env::current_dir()?;
+        let base_dir = normalize(&cwd.join(base_dir));
+
+        let mut excludes = GlobSetBuilder::new();
+        let mut excluded_dirs = GlobSetBuilder::new();
+        for pattern in &config.excludes {
+            let glob = anchor_exclude(pattern, config.version);
+            if glob.is_empty() {
+                continue;
+            }
+            for g in [glob.clone(), format!("{}/**", glob)] {
+                excludes.add(build_glob(&g, pattern, config.version)?);
+                excluded_dirs.add(build_glob(&g, pattern, config.version)?);
+            }
+            if let Some(dir) = glob.strip_suffix("/**") {
+                excluded_dirs.add(build_glob(dir, pattern, config.version)?);
+            }
+        }
+
+        Ok(Matcher {
+            cwd,
+            base_dir,
+            version: config.version,
+            includes: build_include_set(&config.includes, config.version)?,
+            excludes: excludes
+                .build()
+                .context("Failed to build exclude glob set")?,
+            excluded_dirs: excluded_dirs
+                .build()
+                .context("Failed to build exclude glob set")?,
+            organize_imports: config.organize_imports,
+            overrides: config
+                .overrides
+                .iter()
+                .map(|o| {
+                    Ok(CompiledOverride {
+                        includes: build_include_set(&o.includes, config.version)?,
+                        excludes: build_include_set(&o.excludes, config.version)?,
+                        enabled: o.enabled,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            use_ignore_file: config.use_ignore_file,
+            extensions: config.extensions.clone(),
+        })
+    }
+
+    /// The absolute directory patterns are relative to.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Ignore files for a walk from [`Matcher::base_dir`], if the config
+    /// honors them (`vcs.useIgnoreFile`).
+    pub fn ignore_files(&self) -> Option<IgnoreFiles> {
+        self.use_ignore_file
+            .then(|| IgnoreFiles::new(&self.base_dir))
+    }
+
+    /// Whether a walk can skip `dir` and everything below it.
+    pub fn is_excluded_dir(&self, dir: &Path) -> bool {
+        match self.relative(dir) {
+            Some(rel) if !rel.as_os_str().is_empty() => self.excluded_dirs.is_match(&rel),
+            _ => false,
+        }
+    }
+
+    /// Whether `path` has a selected extension and the config includes it.
+    /// Nested configs are not considered; see [`is_included_with_nested`].
+    pub fn is_included(&self, path: &Path) -> bool {
+        self.extensions.matches(path) && !self.is_excluded(path) && self.matches_includes(path)
+    }
+
+    /// Whether an exclude pattern matches `path` or a directory above it.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.relative(path)
+            .is_some_and(|rel| self.excludes.is_match(rel))
+    }
+
+    fn matches_includes(&self, path: &Path) -> bool {
+        self.relative(path)
+            .is_some_and(|rel| self.matches(&self.includes, &rel))
+    }
+
+    /// Whether organizeImports is enabled for `path`: the last override matching
+    /// it wins over the top-level setting. Nested configs are not considered; see
+    /// [`is_organize_imports_enabled`].
+    pub fn organize_imports_enabled(&self, path: &Path) -> bool {
+        let Some(rel) = self.relative(path) else {
+            return true;
+        };
+        self.overrides
+            .iter()
+            .rev()
+            .find(|o| self.matches(&o.includes, &rel) && !self.matches(&o.excludes, &rel))
+            .map_or(self.organize_imports, |o| o.enabled)
+    }
+
+    /// Match a relative path against include-style patterns, with the semantics
+    /// of the config's version.
+    fn matches(&self, set: &GlobSet, rel: &Path) -> bool {
+        match self.version {
+            BiomeVersion::V1 => rel
+                .ancestors()
+                .any(|p| !p.as_os_str().is_empty() && set.is_match(p)),
+            BiomeVersion::V2 => set.is_match(rel),
+        }
+    }
+
+    fn relative(&self, path: &Path) -> Option<PathBuf> {
+        let path = normalize(&self.cwd.join(path));
+        path.strip_prefix(&self.base_dir)
+            .ok()
+            .map(Path::to_path_buf)
+    }
+}
+
+/// Turn an exclude pattern into a glob relative to the config directory.
+fn anchor_exclude(pattern: &str, version: BiomeVersion) -> String {
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    // The default ignores apply at any depth in every version.
+    let relative = version == BiomeVersion::V2 && !DEFAULT_IGNORE.contains(&pattern);
+    if pattern.is_empty() || pattern.contains('/') || relative {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    }
+}
+
+fn build_glob(glob: &str, pattern: &str, version: BiomeVersion) -> Result<Glob> {
+    GlobBuilder::new(glob)
+        .literal_separator(version == BiomeVersion::V2)
+        .build()
+        .with_context(|| format!("Invalid exclude pattern: {}", pattern))
+}
+
+/// Read and parse a biome.json. Returns the config and the directory its
+/// patterns are relative to.
+pub fn load_biome_config(config_file: &Path) -> Result<(BiomeFiles, PathBuf)> {
+    let mut sources = Vec::new();
+    let json = load_with_extends(config_file, &mut Vec::new(), &mut sources)?;
+    let base_dir = match config_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::env::current_dir()?,
+    };
+    let mut config = files_from_json(&json, installed_version(&base_dir))
+        .with_context(|| format!("Failed to parse {}", config_file.display()))?;
+    config.sources = sources;
+    Ok((config, base_dir))
+}
+
+/// Read a config and merge in everything it `extends`, in order, with the
+/// config's own settings applied last. `chain` holds the configs currently being
+/// loaded, to detect cycles; every file read is added to `sources`.
+fn load_with_extends(
+    config_file: &Path,
+    chain: &mut Vec<PathBuf>,
+    sources: &mut Vec<PathBuf>,
+) -> Result<serde_json::Value> {
+    let canonical = std::fs::canonicalize(config_file)
+        .with_context(|| format!("Failed to read {}", config_file.display()))?;
+    if let Some(start) = chain.iter().position(|p| *p == canonical) {
+        let cycle: Vec<_> = chain[start..]
+            .iter()
+            .chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        bail!("Circular extends: {}", cycle.join(" -> "));
+    }
+
+    let content = std::fs::read_to_string(config_file)
+        .with_context(|| format!("Failed to read {}", config_file.display()))?;
+    if !sources.contains(&canonical) {
+        sources.push(canonical.clone());
+    }
+    let mut json = jsonc::parse(&content)
+        .with_context(|| format!("Failed to parse {}", config_file.display()))?;
+
+    let extends = match json.as_object_mut().and_then(|o| o.remove("extends")) {
+        Some(serde_json::Value::String(entry)) => vec![entry],
+        Some(serde_json::Value::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|e| e.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if extends.is_empty() {
+        return Ok(json);
+    }
+
+    let dir = canonical.parent().unwrap_or(Path::new("/")).to_path_buf();
+    chain.push(canonical);
+    let mut merged = serde_json::Value::Object(Default::default());
+    for entry in &extends {
+        let path = resolve_extends(entry, &dir).with_context(|| {
+            format!(
+                "Cannot resolve \"{}\" extended from {}",
+                entry,
+                config_file.display()
+            )
+        })?;
+        merge_json(&mut merged, load_with_extends(&path, chain, sources)?);
+    }
+    chain.pop();
+
+    merge_json(&mut merged, json);
+    Ok(merged)
+}
+
+/// Locate an `extends` entry: `//` for the root config, a path relative to the
+/// extending config, or a package in `node_modules` (`pkg`, `@scope/pkg` or
+/// `pkg/sub/path`).
+fn resolve_extends(entry: &str, dir: &Path) -> Result<PathBuf> {
+    if entry == "//" {
+        // Biome's shorthand for the root config of the project.
+        return dir
+            .parent()
+            .and_then(find_biome_config)
+            .filter(|p| !is_nested_config(p))
+            .context("no root configuration found in a parent directory");
+    }
+    let is_path =
+        entry.starts_with("./") || entry.starts_with("../") || Path::new(entry).is_absolute();
+    if is_path {
+        let path = dir.join(entry);
+        if !path.is_file() {
+            bail!("{} does not exist", path.display());
+        }
+        return Ok(path);
+    }
+
+    // "@scope/pkg/sub" → ("@scope/pkg", "sub"); "pkg/sub" → ("pkg", "sub")
+    let name_len = if entry.starts_with('@') { 2 } else { 1 };
+    let parts: Vec<&str> = entry.splitn(name_len + 1, '/').collect();
+    let package = parts[..name_len.min(parts.len())].join("/");
+    let subpath = parts.get(name_len).copied();
+
+    for ancestor in dir.ancestors() {
+        let package_dir = ancestor.join("node_modules").join(&package);
+        if !package_dir.is_dir() {
+            continue;
+        }
+        let export = package_export(&package_dir, subpath);
+        let candidates: Vec<PathBuf> = match (export, subpath) {
+            (Some(target), _) => vec![package_dir.join(target)],
+            (None, Some(sub)) => ["", ".json", ".jsonc"]
+                .iter()
+                .map(|ext| package_dir.join(format!("{}{}", sub, ext)))
+                .collect(),
+            (None, None) => vec![
+                package_dir.join("biome.json"),
+                package_dir.join("biome.jsonc"),
+            ],
+        };
+        return candidates
+            .into_iter()
+            .find(|p| p.is_file())
+            .with_context(|| format!("package {} has no such config", package_dir.display()));
+    }
+    bail!("package {} not found in node_modules", package)
+}
+
+/// The file `package.json` `exports` maps `subpath` (or the package root) to.
+fn package_export(package_dir: &Path, subpath: Option<&str>) -> Option<String> {
+    let content = std::fs::read_to_string(package_dir.join("package.json")).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let key = match subpath {
+        Some(sub) => format!("./{}", sub),
+        None => ".".to_string(),
+    };
+    let target = match json.get("exports")? {
+        serde_json::Value::String(s) if subpath.is_none() => return Some(s.clone()),
+        exports => exports.get(&key)?,
+    };
+    match target {
+        serde_json::Value::String(s) => Some(s.clone()),
+        conditions => ["default", "import", "require"]
+            .iter()
+            .find_map(|c| conditions.get(*c)?.as_str().map(str::to_string)),
+    }
+}
+
+/// Merge `over` into `base` the way Biome merges extended configs: objects key by
+/// key, anything else (including arrays) replaced.
+fn merge_json(base: &mut serde_json::Value, over: serde_json::Value) {
+    match (base, over) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+/// Lexically resolve `.` and `..` components.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn build_include_set(patterns: &[String], version: BiomeVersion) -> Result<GlobSet> {
+    let mut include_builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = pattern.strip_prefix("./").unwrap_or(pattern);
+        include_builder.add(
+            GlobBuilder::new(glob)
+                .literal_separator(version == BiomeVersion::V2)
+                .build()
+                .with_context(|| format!("Invalid include pattern: {}", pattern))?,
+        );
+    }
+    include_builder
+        .build()
+        .context("Failed to build include glob set")
 }
 
 #[cfg(test)]
@@ -216,4 +1033,669 @@ mod tests {
         assert!(config.excludes.contains(&"dist".to_string()));
     }
 
+    #[test]
+    fn test_is_included() {
+        let config =
+            parse_biome_config(r#"{"files": {"include": ["src/**", "!generated"]}}"#).unwrap();
+        let base = Path::new("/repo");
+        assert!(is_included(&config, base, Path::new("/repo/src/a.ts")).unwrap());
+        assert!(is_included(&config, base, Path::new("/repo/lib/../src/a.tsx")).unwrap());
+        assert!(!is_included(&config, base, Path::new("/repo/src/a.css")).unwrap());
+        assert!(!is_included(&config, base, Path::new("/repo/lib/a.ts")).unwrap());
+        assert!(!is_included(&config, base, Path::new("/repo/src/generated/a.ts")).unwrap());
+        assert!(!is_included(&config, base, Path::new("/repo/src/node_modules/x/a.ts")).unwrap());
+        assert!(!is_included(&config, base, Path::new("/elsewhere/src/a.ts")).unwrap());
+    }
+
+    #[test]
+    fn test_exclude_globs() {
+        let config = parse_biome_config(
+            r#"{"files": {"include": ["**", "!src/generated/**", "!**/*.test.ts", "!dist/legacy"]}}"#,
+        )
+        .unwrap();
+        let matcher = Matcher::new(&config, Path::new("/repo")).unwrap();
+        let included = |p: &str| matcher.is_included(&Path::new("/repo").join(p));
+        let pruned = |p: &str| matcher.is_excluded_dir(&Path::new("/repo").join(p));
+
+        assert!(included("src/a.ts"));
+        assert!(!included("src/generated/a.ts"));
+        assert!(!included("src/generated/deep/a.ts"));
+        assert!(!included("src/a.test.ts"));
+        assert!(!included("a.test.ts"));
+        assert!(!included("dist/legacy/a.ts"));
+        assert!(included("dist/a.ts"));
+        assert!(included("dist/legacy.ts"));
+        // Relative to the config directory, not anywhere in the path.
+        assert!(included("lib/src/generated/a.ts"));
+        assert!(included("lib/dist/legacy/a.ts"));
+
+        assert!(pruned("src/generated"));
+        assert!(pruned("dist/legacy"));
+        assert!(pruned("src/node_modules"));
+        // Only some files below these directories are excluded.
+        assert!(!pruned("src"));
+        assert!(!pruned("dist"));
+    }
+
+    #[test]
+    fn test_resolve_file_paths_applies_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["src/a.ts", "src/a.test.ts", "src/gen/b.ts", "lib/gen/c.ts"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let config = parse_biome_config(
+            r#"{"files": {"include": ["**", "!**/*.test.ts"], "ignore": ["src/gen"]}}"#,
+        )
+        .unwrap();
+        let files = resolve_file_paths(&config, dir.path()).unwrap();
+        let rel: Vec<_> = files
+            .iter()
+            .map(|f| f.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(rel, [Path::new("lib/gen/c.ts"), Path::new("src/a.ts")]);
+    }
+
+    /// How Biome resolves include patterns, per version:
+    /// (version, pattern, path relative to biome.json, included). A `!` pattern
+    /// is tested as `["**", pattern]`, i.e. as an exclude.
+    const INCLUDE_CONFORMANCE: &[(BiomeVersion, &str, &str, bool)] = &[
+        (BiomeVersion::V1, "src/**", "src/a.ts", true),
+        (BiomeVersion::V1, "src/**", "src/deep/a.ts", true),
+        (BiomeVersion::V1, "src/**", "lib/a.ts", false),
+        (BiomeVersion::V1, "src", "src/a.ts", true),
+        (BiomeVersion::V1, "src", "src/deep/a.ts", true),
+        (BiomeVersion::V1, "src", "srcs/a.ts", false),
+        (BiomeVersion::V1, "src/*", "src/a.ts", true),
+        (BiomeVersion::V1, "src/*", "src/deep/a.ts", true),
+        (BiomeVersion::V1, "./src/**", "src/a.ts", true),
+        (BiomeVersion::V1, "*.ts", "a.ts", true),
+        (BiomeVersion::V1, "*.ts", "src/a.ts", true),
+        (BiomeVersion::V1, "**/*.ts", "src/deep/a.ts", true),
+        (BiomeVersion::V1, "**/*.ts", "src/a.tsx", false),
+        (
+            BiomeVersion::V1,
+            "scripts/**/*.js",
+            "scripts/build/a.js",
+            true,
+        ),
+        (BiomeVersion::V1, "scripts/**/*.js", "src/a.js", false),
+        (BiomeVersion::V1, "!dist", "dist/x.ts", false),
+        (BiomeVersion::V1, "!dist", "a/dist/x.ts", false),
+        (BiomeVersion::V2, "src/**", "src/a.ts", true),
+        (BiomeVersion::V2, "src/**", "src/deep/a.ts", true),
+        (BiomeVersion::V2, "src/**", "lib/a.ts", false),
+        (BiomeVersion::V2, "src", "src/a.ts", false),
+        (BiomeVersion::V2, "src/*", "src/a.ts", true),
+        (BiomeVersion::V2, "src/*", "src/deep/a.ts", false),
+        (BiomeVersion::V2, "./src/**", "src/a.ts", true),
+        (BiomeVersion::V2, "*.ts", "a.ts", true),
+        (BiomeVersion::V2, "*.ts", "src/a.ts", false),
+        (BiomeVersion::V2, "**/*.ts", "a.ts", true),
+        (BiomeVersion::V2, "**/*.ts", "src/deep/a.ts", true),
+        (BiomeVersion::V2, "**/*.ts", "src/a.tsx", false),
+        (
+            BiomeVersion::V2,
+            "src/**/*.test.ts",
+            "src/a/b.test.ts",
+            true,
+        ),
+        (BiomeVersion::V2, "src/**/*.test.ts", "src/a/b.ts", false),
+        (BiomeVersion::V2, "**", "a.ts", true),
+        (BiomeVersion::V2, "!dist", "dist/x.ts", false),
+        (BiomeVersion::V2, "!dist", "a/dist/x.ts", true),
+        (BiomeVersion::V2, "!test.ts", "test.ts", false),
+        (BiomeVersion::V2, "!test.ts", "src/test.ts", true),
+        (BiomeVersion::V2, "!**/dist", "a/dist/x.ts", false),
+    ];
+
+    #[test]
+    fn test_include_conformance() {
+        for &(version, pattern, path, expected) in INCLUDE_CONFORMANCE {
+            let (includes, excludes) = match pattern.strip_prefix('!') {
+                Some(excluded) => (vec!["**".to_string()], vec![excluded.to_string()]),
+                None => (vec![pattern.to_string()], Vec::new()),
+            };
+            let config = BiomeFiles {
+                includes,
+                excludes,
+                version,
+                import_groups: ImportGroups::default(),
+                organize_imports: true,
+                overrides: Vec::new(),
+                use_ignore_file: false,
+                max_size: DEFAULT_MAX_SIZE,
+                extensions: Extensions::default(),
+                warnings: Vec::new(),
+                sources: Vec::new(),
+            };
+            let matcher = Matcher::new(&config, Path::new("/repo")).unwrap();
+            assert_eq!(
+                matcher.is_included(&Path::new("/repo").join(path)),
+                expected,
+                "{:?} {:?} {:?}",
+                version,
+                pattern,
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_jsonc_config() {
+        let config = parse_biome_config(
+            r#"{
+                // Biome accepts comments in biome.json too
+                "files": { "includes": ["src/**", /* "lib/**", */], },
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.includes, vec!["src/**"]);
+
+        let err = parse_biome_config("{\n  \"files\": {,}\n}").unwrap_err();
+        assert!(err.to_string().contains("line 2, column 13"), "{}", err);
+    }
+
+    fn write(dir: &Path, file: &str, content: &str) -> PathBuf {
+        let path = dir.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_biome_config_extends() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "biome.base.json",
+            r#"{"files": {"include": ["src/**"], "ignore": ["dist"]}}"#,
+        );
+        write(
+            root,
+            "node_modules/@org/shared/package.json",
+            r#"{"name": "@org/shared", "exports": {"./biome": "./config/biome.json"}}"#,
+        );
+        write(
+            root,
+            "node_modules/@org/shared/config/biome.json",
+            r#"{"files": {"ignore": ["generated"]}}"#,
+        );
+        let config_file = write(
+            root,
+            "packages/app/biome.json",
+            r#"{
+                // later entries win; the config's own settings win over both
+                "extends": ["../../biome.base.json", "@org/shared/biome"],
+                "files": {"include": ["lib/**"]}
+            }"#,
+        );
+
+        let (config, _) = load_biome_config(&config_file).unwrap();
+        assert_eq!(config.includes, vec!["lib/**"]);
+        assert!(config.excludes.contains(&"generated".to_string()));
+        // Arrays are replaced, not concatenated.
+        assert!(!config.excludes.contains(&"dist".to_string()));
+
+        let root = std::fs::canonicalize(root).unwrap();
+        assert_eq!(
+            config.sources,
+            [
+                root.join("packages/app/biome.json"),
+                root.join("biome.base.json"),
+                root.join("node_modules/@org/shared/config/biome.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_biome_config_extends_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let a = write(root, "a.json", r#"{"extends": ["./b.json"]}"#);
+        write(root, "b.json", r#"{"extends": "./a.json"}"#);
+        let err = format!("{:#}", load_biome_config(&a).unwrap_err());
+        assert!(err.contains("Circular extends"), "{}", err);
+        assert!(
+            err.contains("a.json -> ") && err.contains("b.json -> "),
+            "{}",
+            err
+        );
+
+        let c = write(root, "c.json", r#"{"extends": ["./missing.json"]}"#);
+        let err = format!("{:#}", load_biome_config(&c).unwrap_err());
+        assert!(err.contains("Cannot resolve \"./missing.json\""), "{}", err);
+        assert!(err.contains("does not exist"), "{}", err);
+
+        let d = write(root, "d.json", r#"{"extends": ["no-such-package"]}"#);
+        let err = format!("{:#}", load_biome_config(&d).unwrap_err());
+        assert!(err.contains("package no-such-package not found"), "{}", err);
+    }
+
+    #[test]
+    fn test_nested_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "biome.json",
+            r#"{"files": {"includes": ["src/**", "!**/dist"]}}"#,
+        );
+        let nested = r#"{"root": false, "files": {"includes": ["lib/**"]}}"#;
+        write(root, "packages/app/biome.json", nested);
+        for file in [
+            "src/a.ts",
+            "packages/app/lib/b.ts",
+            "packages/app/src/c.ts",
+            "packages/app/lib/dist/d.ts",
+            "packages/other/src/e.ts",
+            "packages/other/lib/f.ts",
+        ] {
+            write(root, file, "");
+        }
+
+        // Discovery from inside a package finds the root config.
+        let config_file = find_biome_config(&root.join("packages/app/lib")).unwrap();
+        assert_eq!(config_file, root.join("biome.json"));
+
+        let (config, base_dir) = load_biome_config(&config_file).unwrap();
+        let files = resolve_file_paths(&config, &base_dir).unwrap();
+        let rel: Vec<_> = files
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            rel,
+            [Path::new("packages/app/lib/b.ts"), Path::new("src/a.ts")]
+        );
+
+        for file in [
+            "packages/app/lib/b.ts",
+            "packages/app/src/c.ts",
+            "packages/app/lib/dist/d.ts",
+            "packages/other/src/e.ts",
+        ] {
+            assert_eq!(
+                is_included(&config, &base_dir, &root.join(file)).unwrap(),
+                rel.contains(&PathBuf::from(file)),
+                "{}",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_config_extends_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "biome.json",
+            r#"{"files": {"ignore": ["generated"]}}"#,
+        );
+        let config_file = write(
+            root,
+            "packages/app/biome.json",
+            r#"{"root": false, "extends": "//", "files": {"includes": ["src/**"]}}"#,
+        );
+
+        let (config, _) = load_biome_config(&config_file).unwrap();
+        assert_eq!(config.includes, vec!["src/**"]);
+        assert!(config.excludes.contains(&"generated".to_string()));
+    }
+
+    #[test]
+    fn test_nested_squeeze_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let config_file = write(
+            root,
+            "biome.json",
+            r#"{"files": {"includes": ["**"], "maxSize": 1000}}"#,
+        );
+        write(
+            root,
+            "packages/app/biome.json",
+            r#"{
+                "root": false,
+                "extends": "//",
+                "assist": { "actions": { "source": { "organizeImports": {
+                    "level": "on",
+                    "options": { "groups": [":NODE:", ":BLANK_LINE:", ":PACKAGE:"] }
+                } } } }
+            }"#,
+        );
+
+        let (config, base_dir) = load_biome_config(&config_file).unwrap();
+        let matcher = Matcher::new(&config, &base_dir).unwrap();
+        assert!(nested_squeeze_options(&matcher, &root.join("src/a.ts"))
+            .unwrap()
+            .is_none());
+        let options = nested_squeeze_options(&matcher, &root.join("packages/app/src/a.ts"))
+            .unwrap()
+            .unwrap();
+        assert!(options.groups.blank_line_between("node:fs", "react"));
+        // The rest comes from the root config it extends.
+        assert_eq!(options.max_size, Some(1000));
+    }
+
+    #[test]
+    fn test_parse_config_version() {
+        let v1 = parse_biome_config(r#"{"files": {"include": ["src/**"]}}"#).unwrap();
+        assert_eq!(v1.version, BiomeVersion::V1);
+        let v2 = parse_biome_config(r#"{"files": {"includes": ["src/**"]}}"#).unwrap();
+        assert_eq!(v2.version, BiomeVersion::V2);
+    }
+
+    #[test]
+    fn test_parse_config_import_groups() {
+        let json = r#"{
+            "assist": { "actions": { "source": { "organizeImports": {
+                "level": "on",
+                "options": { "groups": [":NODE:", ":BLANK_LINE:", ":PACKAGE:"] }
+            } } } }
+        }"#;
+        let config = parse_biome_config(json).unwrap();
+        assert!(config.import_groups.blank_line_between("node:fs", "react"));
+
+        let config = parse_biome_config("{}").unwrap();
+        assert!(config.import_groups.keeps_no_blank_lines());
+
+        let json = r#"{ "assist": { "actions": { "source": { "organizeImports": {
+            "options": { "groups": [":NOPE:"] }
+        } } } } }"#;
+        let err = parse_biome_config(json).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid organizeImports groups");
+    }
+
+    #[test]
+    fn test_organize_imports_overrides() {
+        let json = r#"{
+            "files": { "includes": ["**"] },
+            "overrides": [
+                { "includes": ["legacy/**"], "assist": { "enabled": false } },
+                { "includes": ["legacy/keep/**"], "assist": { "actions": { "source": { "organizeImports": "on" } } } },
+                { "includes": ["vendor/**", "!vendor/own/**"], "linter": { "enabled": false } },
+                { "includes": ["src/**"], "formatter": { "enabled": false } }
+            ]
+        }"#;
+        let config = parse_biome_config(json).unwrap();
+        assert!(config.organize_imports);
+        assert_eq!(config.overrides.len(), 3);
+
+        let matcher = Matcher::new(&config, Path::new("/repo")).unwrap();
+        for (path, expected) in [
+            ("/repo/src/a.ts", true),
+            ("/repo/legacy/a.ts", false),
+            ("/repo/legacy/keep/a.ts", true),
+            ("/repo/vendor/a.ts", false),
+            ("/repo/vendor/own/a.ts", true),
+            ("/elsewhere/legacy/a.ts", true),
+        ] {
+            assert_eq!(
+                matcher.organize_imports_enabled(Path::new(path)),
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        // Biome 1.x: top-level switch, `include`/`ignore` in overrides.
+        let json = r#"{
+            "organizeImports": { "enabled": false },
+            "overrides": [
+                { "include": ["src"], "ignore": ["src/gen"], "organizeImports": { "enabled": true } }
+            ]
+        }"#;
+        let config = parse_biome_config(json).unwrap();
+        let matcher = Matcher::new(&config, Path::new("/repo")).unwrap();
+        assert!(matcher.organize_imports_enabled(Path::new("/repo/src/a.ts")));
+        assert!(!matcher.organize_imports_enabled(Path::new("/repo/src/gen/a.ts")));
+        assert!(!matcher.organize_imports_enabled(Path::new("/repo/lib/a.ts")));
+    }
+
+    #[test]
+    fn test_organize_imports_overrides_in_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "biome.json",
+            r#"{"overrides": [{"includes": ["**/generated/**"], "assist": {"actions": {"source": {"organizeImports": "off"}}}}]}"#,
+        );
+        write(
+            root,
+            "packages/app/biome.json",
+            r#"{"root": false, "assist": {"enabled": false}}"#,
+        );
+        for file in ["src/a.ts", "src/generated/b.ts", "packages/app/c.ts"] {
+            write(root, file, "");
+        }
+
+        let (config, base_dir) = load_biome_config(&root.join("biome.json")).unwrap();
+        let files = resolve_file_paths(&config, &base_dir).unwrap();
+        assert_eq!(files, [root.join("src/a.ts")]);
+
+        let matcher = Matcher::new(&config, &base_dir).unwrap();
+        for (file, expected) in [
+            ("src/a.ts", true),
+            ("src/generated/b.ts", false),
+            ("packages/app/c.ts", false),
+        ] {
+            let path = root.join(file);
+            assert_eq!(is_included(&config, &base_dir, &path).unwrap(), expected);
+            assert_eq!(
+                is_organize_imports_enabled(&matcher, &path).unwrap(),
+                expected,
+                "{}",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_version() {
+        for (version, expected) in [
+            ("1.9.4", Some(BiomeVersion::V1)),
+            ("2.0.0-beta.1", Some(BiomeVersion::V2)),
+            ("@biomejs/biome@2.1.0", Some(BiomeVersion::V2)),
+            ("schema.json", None),
+            ("2", None),
+            ("biomejs.dev", None),
+        ] {
+            assert_eq!(parse_version(version), expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn test_detect_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        // Keys alone would say 2.x; the installed package says 1.x.
+        let config_file = write(root, "biome.json", r#"{"files": {"includes": ["src/**"]}}"#);
+        write(
+            root,
+            "node_modules/@biomejs/biome/package.json",
+            r#"{"name": "@biomejs/biome", "version": "1.9.4"}"#,
+        );
+        let (config, _) = load_biome_config(&config_file).unwrap();
+        assert_eq!(config.version, BiomeVersion::V1);
+        assert_eq!(config.includes, vec!["**"]);
+        assert_eq!(
+            config.warnings,
+            ["`files.includes` is not supported by Biome 1.x and is ignored"]
+        );
+
+        // `$schema` wins over the installed package.
+        write(
+            root,
+            "biome.json",
+            r#"{"$schema": "https://biomejs.dev/schemas/2.1.2/schema.json", "files": {"includes": ["src/**"]}}"#,
+        );
+        let (config, _) = load_biome_config(&config_file).unwrap();
+        assert_eq!(config.version, BiomeVersion::V2);
+        assert!(config.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_version_specific_patterns() {
+        let v2 = parse_biome_config(
+            r#"{
+                "$schema": "https://biomejs.dev/schemas/2.0.0/schema.json",
+                "files": {
+                    "includes": ["**", "!**/*.test.ts", "!!dist"],
+                    "ignore": ["build"]
+                },
+                "organizeImports": { "enabled": false },
+                "overrides": [{ "include": ["legacy/**"], "assist": { "enabled": false } }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(v2.includes, vec!["**"]);
+        assert_eq!(
+            v2.excludes,
+            vec!["node_modules", ".git", "**/*.test.ts", "dist"]
+        );
+        assert!(v2.organize_imports);
+        assert_eq!(v2.overrides[0].includes, vec!["**"]);
+        assert_eq!(
+            v2.warnings,
+            [
+                "`files.ignore` is not supported by Biome 2.x and is ignored",
+                "`organizeImports` is not supported by Biome 2.x and is ignored",
+                "`overrides[0].include` is not supported by Biome 2.x and is ignored",
+            ]
+        );
+
+        let v1 = parse_biome_config(
+            r#"{
+                "$schema": "https://biomejs.dev/schemas/1.9.4/schema.json",
+                "files": { "include": ["src/**", "!src/gen/**"], "ignore": ["dist"] }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(v1.includes, vec!["src/**"]);
+        assert_eq!(v1.excludes, vec!["node_modules", ".git", "dist"]);
+        assert_eq!(
+            v1.warnings,
+            ["files.include: \"!src/gen/**\" is not a negation in Biome 1.x and is ignored; use `ignore` instead"]
+        );
+
+        // Without a known version, both are read leniently.
+        let unknown =
+            parse_biome_config(r#"{"files": {"include": ["**", "!gen"], "ignore": ["dist"]}}"#)
+                .unwrap();
+        assert_eq!(
+            unknown.excludes,
+            vec!["node_modules", ".git", "gen", "dist"]
+        );
+        assert!(unknown.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_exclude_separator_follows_version() {
+        let base = Path::new("/repo");
+        let v1 = parse_biome_config(
+            r#"{
+                "$schema": "https://biomejs.dev/schemas/1.9.4/schema.json",
+                "files": { "ignore": ["src/*.ts"] }
+            }"#,
+        )
+        .unwrap();
+        // In Biome 1.x, `*` crosses `/`.
+        assert!(!is_included(&v1, base, &base.join("src/a.ts")).unwrap());
+        assert!(!is_included(&v1, base, &base.join("src/a/b.ts")).unwrap());
+
+        let v2 = parse_biome_config(r#"{"files": {"includes": ["**", "!src/*.ts"]}}"#).unwrap();
+        assert!(!is_included(&v2, base, &base.join("src/a.ts")).unwrap());
+        assert!(is_included(&v2, base, &base.join("src/a/b.ts")).unwrap());
+        // The default ignores still apply at any depth.
+        assert!(!is_included(&v2, base, &base.join("src/node_modules/a.ts")).unwrap());
+    }
+
+    #[test]
+    fn test_use_ignore_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let config_file = write(
+            root,
+            "biome.json",
+            r#"{"vcs": {"enabled": true, "clientKind": "git", "useIgnoreFile": true}}"#,
+        );
+        write(root, ".gitignore", "build/\n");
+        write(root, "src/.gitignore", "*.gen.ts\n");
+        for file in ["src/a.ts", "src/b.gen.ts", "build/c.ts", "lib/d.gen.ts"] {
+            write(root, file, "");
+        }
+
+        let (config, base_dir) = load_biome_config(&config_file).unwrap();
+        assert!(config.use_ignore_file);
+        let files = resolve_file_paths(&config, &base_dir).unwrap();
+        assert_eq!(files, [root.join("lib/d.gen.ts"), root.join("src/a.ts")]);
+        for (file, expected) in [
+            ("src/a.ts", true),
+            ("src/b.gen.ts", false),
+            ("build/c.ts", false),
+        ] {
+            assert_eq!(
+                is_included(&config, &base_dir, &root.join(file)).unwrap(),
+                expected,
+                "{}",
+                file
+            );
+        }
+
+        // `useIgnoreFile` does nothing without `vcs.enabled`.
+        let config = parse_biome_config(r#"{"vcs": {"useIgnoreFile": true}}"#).unwrap();
+        assert!(!config.use_ignore_file);
+        assert_eq!(resolve_file_paths(&config, root).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_parse_config_max_size() {
+        let config = parse_biome_config(r#"{"files": {"maxSize": 2048}}"#).unwrap();
+        assert_eq!(config.max_size, 2048);
+        assert_eq!(config.squeeze_options().max_size, Some(2048));
+        assert_eq!(parse_biome_config("{}").unwrap().max_size, DEFAULT_MAX_SIZE);
+
+        let err = parse_biome_config(r#"{"files": {"maxSize": "1MB"}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "files.maxSize must be a number of bytes, not \"1MB\""
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        let mut config = parse_biome_config("{}").unwrap();
+        let base = Path::new("/repo");
+        for path in [
+            "a.ts", "a.d.ts", "a.mts", "a.cts", "a.jsx", "a.mjs", "a.cjs",
+        ] {
+            assert!(
+                is_included(&config, base, &base.join(path)).unwrap(),
+                "{}",
+                path
+            );
+        }
+        assert!(!is_included(&config, base, &base.join("a.vue")).unwrap());
+
+        let mut extensions = Extensions::default();
+        extensions.update(".vue").unwrap();
+        extensions.update("!cjs").unwrap();
+        extensions.update("!svelte").unwrap();
+        Adjustments {
+            extensions,
+            ..Default::default()
+        }
+        .apply(&mut config);
+        assert!(is_included(&config, base, &base.join("a.vue")).unwrap());
+        assert!(!is_included(&config, base, &base.join("a.cjs")).unwrap());
+
+        let err = Extensions::default().update("d.ts").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid extension \"d.ts\"");
+        assert!(Extensions::default().update("!").is_err());
+    }
 }
@@ -0,0 +1,162 @@
+//! Unified diff rendering for `--diff`.
+
+use crate::split_lines_inclusive;
+
+/// Lines of unchanged context shown around each change.
+const CONTEXT: usize = 3;
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Render a unified diff of removing `removed_lines` (1-based, ascending) from
+/// `original`, labelled with `path`. Returns an empty string if no line is
+/// removed.
+///
+/// Squeezing only ever deletes whole lines, so the hunks follow directly from
+/// the removed line numbers.
+pub fn unified_diff(path: &str, original: &str, removed_lines: &[usize], color: bool) -> String {
+    if removed_lines.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<&str> = split_lines_inclusive(original).collect();
+    let mut removed = vec![false; lines.len()];
+    for &line in removed_lines {
+        removed[line - 1] = true;
+    }
+
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+
+    let mut out = String::new();
+    out.push_str(&paint(BOLD, &format!("--- {}", path)));
+    out.push('\n');
+    out.push_str(&paint(BOLD, &format!("+++ {}", path)));
+    out.push('\n');
+
+    // Lines removed by earlier hunks, to map old line numbers to new ones.
+    let mut removed_before = 0;
+    for (start, end) in hunk_ranges(removed_lines, lines.len()) {
+        let old_count = end - start;
+        let deleted = removed[start..end].iter().filter(|&&r| r).count();
+        let new_count = old_count - deleted;
+        // An empty range is reported as starting at the line before it.
+        let new_start = start - removed_before + usize::from(new_count > 0);
+        removed_before += deleted;
+        out.push_str(&paint(
+            CYAN,
+            &format!(
+                "@@ -{},{} +{},{} @@",
+                start + 1,
+                old_count,
+                new_start,
+                new_count
+            ),
+        ));
+        out.push('\n');
+
+        for (line, removed) in lines[start..end].iter().zip(&removed[start..end]) {
+            let text = line.trim_end_matches(['\r', '\n']);
+            if *removed {
+                out.push_str(&paint(RED, &format!("-{}", text)));
+            } else {
+                out.push_str(&format!(" {}", text));
+            }
+            out.push('\n');
+            if !line.ends_with(['\n', '\r']) {
+                out.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    out
+}
+
+/// Group removed lines into hunks: each removal plus `CONTEXT` lines around it,
+/// merging hunks whose context would overlap. Returns half-open, 0-based ranges
+/// of the original's `len` lines.
+fn hunk_ranges(removed_lines: &[usize], len: usize) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &line in removed_lines {
+        let i = line - 1;
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(len);
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_is_empty() {
+        assert_eq!(unified_diff("a.ts", "x\ny\n", &[], false), "");
+    }
+
+    #[test]
+    fn test_removed_blank_line() {
+        let original = "import a from 'a'\n\nimport b from 'b'\n\nconst x = 1\n";
+        let expected = concat!(
+            "--- src/a.ts\n",
+            "+++ src/a.ts\n",
+            "@@ -1,5 +1,4 @@\n",
+            " import a from 'a'\n",
+            "-\n",
+            " import b from 'b'\n",
+            " \n",
+            " const x = 1\n",
+        );
+        assert_eq!(unified_diff("src/a.ts", original, &[2], false), expected);
+    }
+
+    #[test]
+    fn test_separate_hunks() {
+        let original = "a\n\nb\n1\n2\n3\n4\n5\n6\n7\nc\n\nd\n";
+        let diff = unified_diff("f", original, &[2, 12], false);
+        assert!(diff.contains("@@ -1,5 +1,4 @@\n"), "{}", diff);
+        assert!(diff.contains("@@ -9,5 +8,4 @@\n"), "{}", diff);
+    }
+
+    #[test]
+    fn test_adjacent_removals_and_crlf() {
+        let diff = unified_diff("f", "a\r\n\r\n\r\nc\r\n", &[2, 3], false);
+        assert_eq!(diff, "--- f\n+++ f\n@@ -1,4 +1,2 @@\n a\n-\n-\n c\n");
+    }
+
+    #[test]
+    fn test_no_newline_at_end_of_file() {
+        let diff = unified_diff("f", "a\n\nb", &[2], false);
+        assert_eq!(
+            diff,
+            "--- f\n+++ f\n@@ -1,3 +1,2 @@\n a\n-\n b\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_color() {
+        let diff = unified_diff("f", "a\n\nb\n", &[2], true);
+        assert!(diff.contains("\x1b[31m-\x1b[0m\n"), "{:?}", diff);
+        assert!(
+            diff.contains("\x1b[36m@@ -1,3 +1,2 @@\x1b[0m\n"),
+            "{:?}",
+            diff
+        );
+    }
+
+    #[test]
+    fn test_every_line_removed() {
+        let diff = unified_diff("f", "\n\n", &[1, 2], false);
+        assert_eq!(diff, "--- f\n+++ f\n@@ -1,2 +0,0 @@\n-\n-\n");
+    }
+}
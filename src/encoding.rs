@@ -0,0 +1,197 @@
+//! Byte-level decoding and encoding of source files.
+//!
+//! Files are decoded to a `String` for processing and re-encoded exactly as they
+//! were found: a UTF-8 BOM stays a UTF-8 BOM, UTF-16 stays UTF-16 in the same
+//! byte order.
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// Number of leading bytes inspected when sniffing BOM-less UTF-16.
+const SNIFF_LEN: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// How a file was encoded on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Encoding {
+    pub charset: Charset,
+    /// Whether the file started with a byte order mark.
+    pub bom: bool,
+}
+
+impl Encoding {
+    pub const UTF8: Encoding = Encoding::new(Charset::Utf8, false);
+
+    pub const fn new(charset: Charset, bom: bool) -> Self {
+        Encoding { charset, bom }
+    }
+}
+
+/// Decode file bytes. Returns the text without any BOM, and the encoding needed
+/// to write it back. The error is a human-readable reason the file was skipped.
+pub fn decode(bytes: &[u8]) -> Result<(String, Encoding), String> {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        let text = std::str::from_utf8(rest)
+            .map_err(|e| format!("invalid UTF-8 after BOM at byte {}", e.valid_up_to() + 3))?;
+        return Ok((text.to_string(), Encoding::new(Charset::Utf8, true)));
+    }
+
+    let (body, charset, bom) = if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+        (rest, Charset::Utf16Le, true)
+    } else if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+        (rest, Charset::Utf16Be, true)
+    } else if let Some(charset) = sniff_utf16(bytes) {
+        (bytes, charset, false)
+    } else {
+        return match std::str::from_utf8(bytes) {
+            Ok(text) => Ok((text.to_string(), Encoding::UTF8)),
+            Err(e) => Err(format!(
+                "not valid UTF-8 (invalid byte at offset {})",
+                e.valid_up_to()
+            )),
+        };
+    };
+
+    decode_utf16(body, charset).map(|text| (text, Encoding::new(charset, bom)))
+}
+
+/// Encode `text` back into the encoding it was read with.
+pub fn encode(text: &str, encoding: Encoding) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len() + 3);
+    match encoding.charset {
+        Charset::Utf8 => {
+            if encoding.bom {
+                out.extend_from_slice(UTF8_BOM);
+            }
+            out.extend_from_slice(text.as_bytes());
+        }
+        Charset::Utf16Le => {
+            if encoding.bom {
+                out.extend_from_slice(UTF16_LE_BOM);
+            }
+            for unit in text.encode_utf16() {
+                out.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        Charset::Utf16Be => {
+            if encoding.bom {
+                out.extend_from_slice(UTF16_BE_BOM);
+            }
+            for unit in text.encode_utf16() {
+                out.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+    }
+    out
+}
+
+fn decode_utf16(bytes: &[u8], charset: Charset) -> Result<String, String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err("truncated UTF-16 (odd number of bytes)".to_string());
+    }
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| match charset {
+            Charset::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+            _ => u16::from_le_bytes([pair[0], pair[1]]),
+        })
+        .collect();
+    String::from_utf16(&units).map_err(|_| "invalid UTF-16 (unpaired surrogate)".to_string())
+}
+
+/// Detect BOM-less UTF-16 from the NUL bytes that ASCII text leaves in every
+/// other position. Source code is overwhelmingly ASCII, so this is reliable.
+fn sniff_utf16(bytes: &[u8]) -> Option<Charset> {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if sample.len() < 4 || !sample.len().is_multiple_of(2) {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    if odd_nuls * 2 >= pairs && even_nuls == 0 {
+        Some(Charset::Utf16Le)
+    } else if even_nuls * 2 >= pairs && odd_nuls == 0 {
+        Some(Charset::Utf16Be)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        encode(text, Encoding::new(Charset::Utf16Le, bom))
+    }
+
+    #[test]
+    fn test_plain_utf8_roundtrip() {
+        let (text, enc) = decode(b"import a from 'a'\n").unwrap();
+        assert_eq!(text, "import a from 'a'\n");
+        assert_eq!(enc, Encoding::UTF8);
+        assert_eq!(encode(&text, enc), b"import a from 'a'\n");
+    }
+
+    #[test]
+    fn test_utf8_bom_stripped_and_restored() {
+        let bytes = b"\xEF\xBB\xBFimport a from 'a'\n";
+        let (text, enc) = decode(bytes).unwrap();
+        assert_eq!(text, "import a from 'a'\n");
+        assert!(enc.bom);
+        assert_eq!(encode(&text, enc), bytes);
+    }
+
+    #[test]
+    fn test_utf16_le_with_bom_roundtrip() {
+        let bytes = utf16le("import a from 'a'\n", true);
+        assert!(bytes.starts_with(UTF16_LE_BOM));
+        let (text, enc) = decode(&bytes).unwrap();
+        assert_eq!(text, "import a from 'a'\n");
+        assert_eq!(enc, Encoding::new(Charset::Utf16Le, true));
+        assert_eq!(encode(&text, enc), bytes);
+    }
+
+    #[test]
+    fn test_utf16_be_with_bom_roundtrip() {
+        let enc = Encoding::new(Charset::Utf16Be, true);
+        let bytes = encode("import a from 'a'\n", enc);
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            ("import a from 'a'\n".to_string(), enc)
+        );
+    }
+
+    #[test]
+    fn test_utf16_without_bom_sniffed() {
+        let bytes = utf16le("import a from 'a'\n", false);
+        let (text, enc) = decode(&bytes).unwrap();
+        assert_eq!(text, "import a from 'a'\n");
+        assert_eq!(enc, Encoding::new(Charset::Utf16Le, false));
+    }
+
+    #[test]
+    fn test_latin1_is_rejected() {
+        let err = decode(b"// caf\xE9\nimport a from 'a'\n").unwrap_err();
+        assert!(err.contains("not valid UTF-8"), "{}", err);
+    }
+
+    #[test]
+    fn test_unpaired_surrogate_is_rejected() {
+        let err = decode(&[0xFF, 0xFE, 0x00, 0xD8]).unwrap_err();
+        assert!(err.contains("UTF-16"), "{}", err);
+    }
+}
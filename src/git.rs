@@ -0,0 +1,791 @@
+//! Git integration: squeezing staged content in place (`--staged`), limiting a
+//! run to files changed against a branch (`--changed-since`), and finding the
+//! changed lines within them (`--changed-lines-only`).
+//!
+//! Everything goes through the `git` executable, so hooks, worktrees and
+//! `core.*` settings behave exactly as they do for the user's own git commands.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::{self, Extensions, Matcher};
+use crate::report::FileReport;
+use crate::{process_bytes_with_options, FileResult, SqueezeOptions};
+
+/// Outcome of [`squeeze_staged`].
+#[derive(Debug, Default)]
+pub struct Staged {
+    pub reports: Vec<FileReport>,
+    /// Rendered diff per report, when one was requested.
+    pub diffs: Vec<Option<String>>,
+    /// Non-fatal problems, e.g. a worktree file that couldn't be patched.
+    pub warnings: Vec<String>,
+}
+
+/// Squeeze the staged (index) content of every staged file with one of
+/// `extensions` in the repository containing `dir`.
+///
+/// The squeezed content is written back to the index, and the same change is
+/// applied to the worktree file as a patch, so unstaged hunks survive. If the
+/// patch doesn't apply (the unstaged edits touch the same lines), the worktree
+/// file is left alone and a warning is recorded. With `check`, nothing is written.
+/// With a `config`, only files it would select (see
+/// [`config::is_included_with_nested`]) are squeezed.
+pub fn squeeze_staged(
+    dir: &Path,
+    check: bool,
+    diff_color: Option<bool>,
+    options: &SqueezeOptions,
+    extensions: &Extensions,
+    config: Option<&Matcher>,
+) -> Result<Staged> {
+    let root = toplevel(dir)?;
+    let names = git(
+        &root,
+        [
+            "diff",
+            "--cached",
+            "--name-only",
+            "--diff-filter=ACMR",
+            "--no-renames",
+            "-z",
+        ],
+        None,
+    )?;
+
+    let mut staged = Staged::default();
+    for name in split_nul(&names) {
+        let rel = PathBuf::from(name);
+        if !extensions.matches(&rel) {
+            continue;
+        }
+        let mut nested = None;
+        if let Some(matcher) = config {
+            let path = root.join(&rel);
+            if !config::is_included_with_nested(matcher, &path)? {
+                continue;
+            }
+            nested = config::nested_squeeze_options(matcher, &path)?;
+        }
+        let options = nested.as_ref().unwrap_or(options);
+        let (result, diff) = match squeeze_entry(&root, name, check, diff_color, options) {
+            Ok(Entry {
+                result,
+                diff,
+                warning,
+            }) => {
+                staged.warnings.extend(warning);
+                (Ok(result), diff)
+            }
+            Err(e) => (Err(format!("{:#}", e)), None),
+        };
+        staged.reports.push(FileReport {
+            path: root.join(rel),
+            result,
+        });
+        staged.diffs.push(diff);
+    }
+    Ok(staged)
+}
+
+struct Entry {
+    result: FileResult,
+    diff: Option<String>,
+    warning: Option<String>,
+}
+
+fn squeeze_entry(
+    root: &Path,
+    name: &str,
+    check: bool,
+    diff_color: Option<bool>,
+    options: &SqueezeOptions,
+) -> Result<Entry> {
+    let (mode, object) = index_entry(root, name)?;
+    if !mode.starts_with("100") {
+        // Symlinks and submodules have no source to squeeze.
+        return Ok(Entry {
+            result: FileResult::Skipped(format!("not a regular file (mode {})", mode)),
+            diff: None,
+            warning: None,
+        });
+    }
+
+    let original = git(root, ["cat-file", "blob", &object], None)?;
+    let processed = process_bytes_with_options(&original, name, diff_color, options);
+    let mut warning = None;
+
+    if let (Some(output), false) = (&processed.output, check) {
+        let new_object = git_line(root, ["hash-object", "-w", "--stdin"], Some(output))?;
+        let cacheinfo = format!("{},{},{}", mode, new_object, name);
+        git(root, ["update-index", "--cacheinfo", &cacheinfo], None)?;
+
+        if let Err(e) = patch_worktree(root, name, &object, &new_object) {
+            warning = Some(format!(
+                "{}: staged content was squeezed, but the working tree file could not be updated: {:#}",
+                name, e
+            ));
+        }
+    }
+
+    Ok(Entry {
+        result: processed.result,
+        diff: processed.diff,
+        warning,
+    })
+}
+
+/// Mode and object id of the stage-0 index entry for `name`.
+///
+/// `name` is passed as a literal pathspec, so names with glob characters
+/// (`app/[id]/page.tsx`) only match themselves.
+fn index_entry(root: &Path, name: &str) -> Result<(String, String)> {
+    let out = git(
+        root,
+        [
+            "--literal-pathspecs",
+            "ls-files",
+            "--stage",
+            "-z",
+            "--",
+            name,
+        ],
+        None,
+    )?;
+    let mut entries = Vec::new();
+    for entry in split_nul(&out) {
+        // "<mode> <object> <stage>\t<path>"
+        let (meta, path) = entry
+            .split_once('\t')
+            .context("Malformed ls-files output")?;
+        let mut fields = meta.split(' ');
+        let (Some(mode), Some(object), Some(stage)) = (fields.next(), fields.next(), fields.next())
+        else {
+            bail!("Malformed ls-files output: {}", meta);
+        };
+        if stage != "0" {
+            bail!("{} has merge conflicts", name);
+        }
+        entries.push((path, mode, object));
+    }
+    match entries.as_slice() {
+        [] => bail!("{} is not in the index", name),
+        [(path, mode, object)] if *path == name => Ok((mode.to_string(), object.to_string())),
+        _ => bail!("{} matches more than one index entry", name),
+    }
+}
+
+/// Apply the index change `old` → `new` to the worktree copy of `name`.
+///
+/// The patch is made from index blobs, so it has the index's line endings;
+/// `git apply` converts it for worktrees checked out with `core.autocrlf` or
+/// `eol` attributes.
+fn patch_worktree(root: &Path, name: &str, old: &str, new: &str) -> Result<()> {
+    let patch = git(
+        root,
+        [
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--binary",
+            SRC_PREFIX,
+            DST_PREFIX,
+            old,
+            new,
+        ],
+        None,
+    )?;
+    let patch = String::from_utf8_lossy(&patch);
+
+    // `git diff <blob> <blob>` names both sides after the object ids; point the
+    // header at the real path so `git apply` patches the worktree file.
+    let mut fixed = String::with_capacity(patch.len());
+    let mut in_header = true;
+    for line in patch.split_inclusive('\n') {
+        if line.starts_with("@@") {
+            in_header = false;
+        }
+        if in_header {
+            fixed.push_str(
+                &line
+                    .replace(&format!("a/{}", old), &format!("a/{}", name))
+                    .replace(&format!("b/{}", new), &format!("b/{}", name)),
+            );
+        } else {
+            fixed.push_str(line);
+        }
+    }
+
+    git(
+        root,
+        ["apply", "--whitespace=nowarn", "-"],
+        Some(fixed.as_bytes()),
+    )?;
+    Ok(())
+}
+
+/// Diff prefixes the patch handling expects, whatever `diff.noprefix` or
+/// `diff.mnemonicPrefix` say.
+const SRC_PREFIX: &str = "--src-prefix=a/";
+const DST_PREFIX: &str = "--dst-prefix=b/";
+
+/// Files added or modified since the merge base of `reference` and `HEAD`,
+/// including uncommitted changes, as absolute paths. Deleted files are left out.
+pub fn changed_since(dir: &Path, reference: &str) -> Result<Vec<PathBuf>> {
+    let root = toplevel(dir)?;
+    let merge_base = merge_base(&root, reference)?;
+    let names = git(
+        &root,
+        [
+            "diff",
+            "--name-only",
+            "--diff-filter=ACMR",
+            "--no-renames",
+            "-z",
+            &merge_base,
+        ],
+        None,
+    )?;
+
+    let mut files: Vec<PathBuf> = split_nul(&names)
+        .map(|name| root.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn merge_base(root: &Path, reference: &str) -> Result<String> {
+    git_line(root, ["merge-base", reference, "HEAD"], None)
+        .with_context(|| format!("Failed to find the merge base of {} and HEAD", reference))
+}
+
+/// Lines of the working tree that differ from a base commit, per file.
+#[derive(Debug, Default)]
+pub struct ChangedLines {
+    /// 1-based, inclusive line ranges in the working tree file, keyed by
+    /// absolute path.
+    ranges: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+    /// Files git doesn't track yet; every line counts as changed.
+    untracked: HashSet<PathBuf>,
+}
+
+impl ChangedLines {
+    /// The changed ranges of `path` (absolute and canonical), or `None` when the
+    /// whole file is new. Files without changes get an empty list.
+    pub fn for_file(&self, path: &Path) -> Option<Vec<RangeInclusive<usize>>> {
+        if self.untracked.contains(path) {
+            return None;
+        }
+        Some(self.ranges.get(path).cloned().unwrap_or_default())
+    }
+}
+
+/// Lines changed in the working tree relative to `HEAD`, or to the merge base
+/// of `reference` and `HEAD` when given.
+pub fn changed_lines(dir: &Path, reference: Option<&str>) -> Result<ChangedLines> {
+    let root = toplevel(dir)?;
+    let base = match reference {
+        Some(reference) => merge_base(&root, reference)?,
+        None => "HEAD".to_string(),
+    };
+    let patch = git(
+        &root,
+        [
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--no-color",
+            "--no-ext-diff",
+            "--no-renames",
+            "--unified=0",
+            SRC_PREFIX,
+            DST_PREFIX,
+            &base,
+        ],
+        None,
+    )?;
+    let untracked = git(
+        &root,
+        ["ls-files", "--others", "--exclude-standard", "-z"],
+        None,
+    )?;
+
+    Ok(ChangedLines {
+        ranges: parse_hunk_ranges(&String::from_utf8_lossy(&patch))
+            .into_iter()
+            .map(|(name, ranges)| (root.join(name), ranges))
+            .collect(),
+        untracked: split_nul(&untracked).map(|name| root.join(name)).collect(),
+    })
+}
+
+/// New-side line ranges of each file in a `--unified=0` patch. A pure deletion
+/// marks the lines on either side of it.
+fn parse_hunk_ranges(patch: &str) -> HashMap<String, Vec<RangeInclusive<usize>>> {
+    let mut files: HashMap<String, Vec<RangeInclusive<usize>>> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in patch.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(str::to_string);
+        } else if let (Some(hunk), Some(name)) = (line.strip_prefix("@@ "), &current) {
+            // "@@ -a[,b] +c[,d] @@"
+            let Some(new) = hunk.split(' ').find_map(|f| f.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, count) = match new.split_once(',') {
+                Some((start, count)) => (start.parse::<usize>(), count.parse::<usize>()),
+                None => (new.parse::<usize>(), Ok(1)),
+            };
+            let (Ok(start), Ok(count)) = (start, count) else {
+                continue;
+            };
+            let range = if count == 0 {
+                start.max(1)..=start + 1
+            } else {
+                start..=start + count - 1
+            };
+            files.entry(name.clone()).or_default().push(range);
+        }
+    }
+    files
+}
+
+/// Absolute path of the work tree containing `dir`.
+pub fn toplevel(dir: &Path) -> Result<PathBuf> {
+    let root = git_line(dir, ["rev-parse", "--show-toplevel"], None)
+        .context("Not inside a git work tree")?;
+    Ok(PathBuf::from(root))
+}
+
+/// Run git in `dir` and return its stdout; a non-zero exit becomes an error
+/// carrying git's stderr.
+fn git<I, S>(dir: &Path, args: I, input: Option<&[u8]>) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let args: Vec<S> = args.into_iter().collect();
+    let display = args
+        .iter()
+        .map(|a| a.as_ref().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut child = Command::new("git")
+        .args(&args)
+        .current_dir(dir)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git")?;
+
+    if let Some(input) = input {
+        // Dropping stdin after writing closes the pipe.
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(input)
+            .with_context(|| format!("Failed to write to git {}", display))?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            display,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Like [`git`], for commands that print a single line.
+fn git_line<I, S>(dir: &Path, args: I, input: Option<&[u8]>) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let out = git(dir, args, input)?;
+    Ok(String::from_utf8_lossy(&out).trim_end().to_string())
+}
+
+/// Entries of `-z` output.
+fn split_nul(out: &[u8]) -> impl Iterator<Item = &str> {
+    out.split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| std::str::from_utf8(s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn init_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), ["init", "-q"], None).unwrap();
+        dir
+    }
+
+    fn commit(root: &Path, message: &str) {
+        git(root, ["add", "-A"], None).unwrap();
+        git(
+            root,
+            [
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                message,
+            ],
+            None,
+        )
+        .unwrap();
+    }
+
+    fn show_index(root: &Path, name: &str) -> String {
+        String::from_utf8(git(root, ["show", &format!(":{}", name)], None).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_squeeze_staged_keeps_unstaged_hunks() {
+        let dir = init_repo();
+        let root = dir.path();
+        fs::write(
+            root.join("a.ts"),
+            "import { a } from 'a'\n\nimport { b } from 'b'\n\nconst x = 1\n",
+        )
+        .unwrap();
+        git(root, ["add", "a.ts"], None).unwrap();
+        // Unstaged edit further down the file.
+        fs::write(
+            root.join("a.ts"),
+            "import { a } from 'a'\n\nimport { b } from 'b'\n\nconst x = 1\nconst y = 2\n",
+        )
+        .unwrap();
+
+        let staged = squeeze_staged(
+            root,
+            false,
+            None,
+            &SqueezeOptions::default(),
+            &Extensions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged.reports.len(), 1);
+        assert_eq!(
+            staged.reports[0].result,
+            Ok(FileResult::Changed {
+                removed_lines: vec![2]
+            })
+        );
+        assert!(staged.warnings.is_empty(), "{:?}", staged.warnings);
+
+        assert_eq!(
+            show_index(root, "a.ts"),
+            "import { a } from 'a'\nimport { b } from 'b'\n\nconst x = 1\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("a.ts")).unwrap(),
+            "import { a } from 'a'\nimport { b } from 'b'\n\nconst x = 1\nconst y = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_squeeze_staged_with_custom_diff_config() {
+        let source = "import { a } from 'a'\n\nimport { b } from 'b'\n\nconst x = 1\n";
+        for config in [
+            ["diff.noprefix", "true"],
+            ["diff.mnemonicPrefix", "true"],
+            ["core.autocrlf", "true"],
+        ] {
+            let dir = init_repo();
+            let root = dir.path();
+            git(root, ["config", config[0], config[1]], None).unwrap();
+            let worktree = |s: &str| match config[0] {
+                "core.autocrlf" => s.replace('\n', "\r\n"),
+                _ => s.to_string(),
+            };
+            fs::write(root.join("a.ts"), worktree(source)).unwrap();
+            git(root, ["add", "a.ts"], None).unwrap();
+            fs::write(
+                root.join("a.ts"),
+                worktree(&format!("{}const y = 2\n", source)),
+            )
+            .unwrap();
+
+            let staged = squeeze_staged(
+                root,
+                false,
+                None,
+                &SqueezeOptions::default(),
+                &Extensions::default(),
+                None,
+            )
+            .unwrap();
+            assert!(
+                staged.warnings.is_empty(),
+                "{:?}: {:?}",
+                config,
+                staged.warnings
+            );
+            assert_eq!(
+                show_index(root, "a.ts"),
+                "import { a } from 'a'\nimport { b } from 'b'\n\nconst x = 1\n"
+            );
+            assert_eq!(
+                fs::read_to_string(root.join("a.ts")).unwrap(),
+                worktree(
+                    "import { a } from 'a'\nimport { b } from 'b'\n\nconst x = 1\nconst y = 2\n"
+                ),
+                "{:?}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn test_squeeze_staged_check_and_filtering() {
+        let dir = init_repo();
+        let root = dir.path();
+        let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/a.ts"), source).unwrap();
+        fs::write(root.join("notes.md"), source).unwrap();
+        fs::write(root.join("unstaged.ts"), source).unwrap();
+        git(root, ["add", "src/a.ts", "notes.md"], None).unwrap();
+
+        // Runs from a subdirectory, reporting paths from the top level.
+        let staged = squeeze_staged(
+            &root.join("src"),
+            true,
+            None,
+            &SqueezeOptions::default(),
+            &Extensions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged.reports.len(), 1);
+        assert!(staged.reports[0].path.ends_with("src/a.ts"));
+        assert!(matches!(
+            staged.reports[0].result,
+            Ok(FileResult::Changed { .. })
+        ));
+
+        // Check mode leaves both index and worktree alone.
+        assert_eq!(show_index(root, "src/a.ts"), source);
+        assert_eq!(fs::read_to_string(root.join("src/a.ts")).unwrap(), source);
+    }
+
+    #[test]
+    fn test_squeeze_staged_applies_config() {
+        let dir = init_repo();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join("src/a.ts"), source).unwrap();
+        fs::write(root.join("dist/a.ts"), source).unwrap();
+        git(&root, ["add", "."], None).unwrap();
+
+        let config =
+            config::parse_biome_config(r#"{"files": {"includes": ["**", "!dist"]}}"#).unwrap();
+        let matcher = Matcher::new(&config, &root).unwrap();
+        let staged = squeeze_staged(
+            &root,
+            true,
+            None,
+            &SqueezeOptions::default(),
+            &Extensions::default(),
+            Some(&matcher),
+        )
+        .unwrap();
+        assert_eq!(staged.reports.len(), 1);
+        assert!(staged.reports[0].path.ends_with("src/a.ts"));
+    }
+
+    #[test]
+    fn test_squeeze_staged_conflicting_worktree_warns() {
+        let dir = init_repo();
+        let root = dir.path();
+        fs::write(
+            root.join("a.ts"),
+            "import { a } from 'a'\n\nimport { b } from 'b'\n",
+        )
+        .unwrap();
+        git(root, ["add", "a.ts"], None).unwrap();
+        // The unstaged edit rewrites the very lines the squeeze touches.
+        let worktree = "import { a } from 'a'\n// note\nimport { c } from 'c'\n";
+        fs::write(root.join("a.ts"), worktree).unwrap();
+
+        let staged = squeeze_staged(
+            root,
+            false,
+            None,
+            &SqueezeOptions::default(),
+            &Extensions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged.warnings.len(), 1);
+        assert_eq!(
+            show_index(root, "a.ts"),
+            "import { a } from 'a'\nimport { b } from 'b'\n"
+        );
+        assert_eq!(fs::read_to_string(root.join("a.ts")).unwrap(), worktree);
+    }
+
+    #[test]
+    fn test_squeeze_staged_path_with_glob_characters() {
+        let dir = init_repo();
+        let root = dir.path();
+        let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+        let other = "import { c } from 'c'\n\nimport { d } from 'd'\n";
+        fs::create_dir_all(root.join("app/[id]")).unwrap();
+        fs::create_dir_all(root.join("app/i")).unwrap();
+        fs::write(root.join("app/[id]/page.tsx"), source).unwrap();
+        // Matched by `app/[id]/page.tsx` as a glob.
+        fs::write(root.join("app/i/page.tsx"), other).unwrap();
+        git(root, ["add", "."], None).unwrap();
+
+        let staged = squeeze_staged(
+            root,
+            false,
+            None,
+            &SqueezeOptions::default(),
+            &Extensions::default(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(staged.reports.len(), 2);
+        for report in &staged.reports {
+            assert!(
+                matches!(report.result, Ok(FileResult::Changed { .. })),
+                "{:?}",
+                report
+            );
+        }
+        assert_eq!(
+            show_index(root, "app/[id]/page.tsx"),
+            "import { a } from 'a'\nimport { b } from 'b'\n"
+        );
+        assert_eq!(
+            show_index(root, "app/i/page.tsx"),
+            "import { c } from 'c'\nimport { d } from 'd'\n"
+        );
+    }
+
+    #[test]
+    fn test_changed_since_merge_base() {
+        let dir = init_repo();
+        let root = dir.path();
+        fs::write(root.join("base.ts"), "export const a = 1\n").unwrap();
+        fs::write(root.join("gone.ts"), "export const b = 1\n").unwrap();
+        commit(root, "base");
+        git(root, ["branch", "main-line"], None).unwrap();
+
+        git(root, ["checkout", "-q", "-b", "feature"], None).unwrap();
+        fs::write(root.join("added.ts"), "export const c = 1\n").unwrap();
+        fs::remove_file(root.join("gone.ts")).unwrap();
+        commit(root, "feature");
+
+        // Commits on the other branch after the fork point don't count.
+        git(root, ["checkout", "-q", "main-line"], None).unwrap();
+        fs::write(root.join("other.ts"), "export const d = 1\n").unwrap();
+        commit(root, "other");
+        git(root, ["checkout", "-q", "feature"], None).unwrap();
+
+        // Uncommitted edits do.
+        fs::write(root.join("base.ts"), "export const a = 2\n").unwrap();
+
+        let files = changed_since(root, "main-line").unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["added.ts", "base.ts"]);
+        assert!(files.iter().all(|f| f.is_absolute()));
+
+        assert!(changed_since(root, "no-such-ref").is_err());
+    }
+
+    #[test]
+    fn test_parse_hunk_ranges() {
+        let patch = "\
+diff --git a/src/a.ts b/src/a.ts
+index 1111111..2222222 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -3 +3 @@ import { a } from 'a'
+-old
++new
+@@ -10,2 +9,0 @@ const x = 1
+@@ -20,0 +19,3 @@ const y = 2
+diff --git a/gone.ts b/gone.ts
+deleted file mode 100644
+--- a/gone.ts
++++ /dev/null
+@@ -1 +0,0 @@
+-x
+";
+        let files = parse_hunk_ranges(patch);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files["src/a.ts"], vec![3..=3, 9..=10, 19..=21]);
+    }
+
+    #[test]
+    fn test_changed_lines_against_head() {
+        let dir = init_repo();
+        let root = dir.path();
+        fs::write(root.join("a.ts"), "a\nb\nc\nd\n").unwrap();
+        fs::write(root.join("same.ts"), "a\n").unwrap();
+        commit(root, "base");
+        fs::write(root.join("a.ts"), "a\nB\nc\nd\ne\n").unwrap();
+        fs::write(root.join("new.ts"), "a\n").unwrap();
+
+        let root = fs::canonicalize(root).unwrap();
+        let changed = changed_lines(&root, None).unwrap();
+        assert_eq!(
+            changed.for_file(&root.join("a.ts")),
+            Some(vec![2..=2, 5..=5])
+        );
+        assert_eq!(changed.for_file(&root.join("same.ts")), Some(vec![]));
+        assert_eq!(changed.for_file(&root.join("new.ts")), None);
+    }
+
+    #[test]
+    fn test_changed_lines_with_custom_diff_prefixes() {
+        for config in ["diff.noprefix", "diff.mnemonicPrefix"] {
+            let dir = init_repo();
+            let root = fs::canonicalize(dir.path()).unwrap();
+            git(&root, ["config", config, "true"], None).unwrap();
+            fs::write(root.join("a.ts"), "a\nb\n").unwrap();
+            commit(&root, "base");
+            fs::write(root.join("a.ts"), "a\nB\n").unwrap();
+
+            let changed = changed_lines(&root, None).unwrap();
+            assert_eq!(
+                changed.for_file(&root.join("a.ts")),
+                Some(vec![2..=2]),
+                "{}",
+                config
+            );
+        }
+    }
+}
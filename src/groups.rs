@@ -0,0 +1,308 @@
+//! Biome 2's `organizeImports` `groups` option, as far as blank lines go.
+//!
+//! Biome sorts imports into the configured groups and puts a blank line wherever
+//! the list has a `":BLANK_LINE:"` marker between two groups. Those blank lines
+//! must survive squeezing, or the two tools undo each other's work forever.
+
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use serde_json::Value;
+
+const BLANK_LINE: &str = ":BLANK_LINE:";
+
+/// Node.js built-in modules, matched by `:NODE:` with or without `node:`.
+const NODE_BUILTINS: &[&str] = &[
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// The configured groups, in order. Empty when the config has none.
+#[derive(Debug, Clone, Default)]
+pub struct ImportGroups {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone)]
+enum Entry {
+    BlankLine,
+    /// A source belongs to the group if it matches any positive matcher and
+    /// none of the negated ones.
+    Group(Vec<(bool, SourceMatcher)>),
+}
+
+#[derive(Debug, Clone)]
+enum SourceMatcher {
+    Predefined(Predefined),
+    Glob(GlobMatcher),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Predefined {
+    Alias,
+    Bun,
+    Node,
+    Package,
+    PackageWithProtocol,
+    Path,
+    Url,
+}
+
+impl ImportGroups {
+    /// Parse the `groups` array of the organizeImports options.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let Some(items) = value.as_array() else {
+            bail!("groups must be an array");
+        };
+        let entries = items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) if s == BLANK_LINE => Ok(Entry::BlankLine),
+                _ => Ok(Entry::Group(parse_group(item)?)),
+            })
+            .collect::<Result<_>>()?;
+        Ok(ImportGroups { entries })
+    }
+
+    /// Whether there are no `:BLANK_LINE:` markers, so every blank line between
+    /// imports can go.
+    pub fn keeps_no_blank_lines(&self) -> bool {
+        !self.entries.iter().any(|e| matches!(e, Entry::BlankLine))
+    }
+
+    /// Whether Biome separates an import of `a` from an adjacent import of `b`
+    /// with a blank line.
+    pub fn blank_line_between(&self, a: &str, b: &str) -> bool {
+        let (a, b) = (self.position(a), self.position(b));
+        let (low, high) = (a.min(b), a.max(b));
+        self.entries[low..high]
+            .iter()
+            .any(|e| matches!(e, Entry::BlankLine))
+    }
+
+    /// Index of the first group `source` belongs to; sources that match no group
+    /// go after all of them.
+    fn position(&self, source: &str) -> usize {
+        self.entries
+            .iter()
+            .position(|entry| match entry {
+                Entry::BlankLine => false,
+                Entry::Group(matchers) => {
+                    let negated = matchers.iter().any(|(neg, m)| *neg && m.is_match(source));
+                    !negated && matchers.iter().any(|(neg, m)| !*neg && m.is_match(source))
+                }
+            })
+            .unwrap_or(self.entries.len())
+    }
+}
+
+fn parse_group(item: &Value) -> Result<Vec<(bool, SourceMatcher)>> {
+    match item {
+        Value::String(s) => Ok(vec![parse_matcher(s)?]),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                let s = item.as_str().context("group matchers must be strings")?;
+                parse_matcher(s)
+            })
+            .collect(),
+        // `{ "source": [...], "type": ... }`: only the source matters here.
+        Value::Object(object) => match object.get("source") {
+            Some(source) => parse_group(source),
+            None => Ok(vec![(false, SourceMatcher::Glob(glob("**")?))]),
+        },
+        _ => bail!("invalid group: {}", item),
+    }
+}
+
+fn parse_matcher(pattern: &str) -> Result<(bool, SourceMatcher)> {
+    let (negated, pattern) = match pattern.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+    let predefined = match pattern {
+        ":ALIAS:" => Some(Predefined::Alias),
+        ":BUN:" => Some(Predefined::Bun),
+        ":NODE:" => Some(Predefined::Node),
+        ":PACKAGE:" => Some(Predefined::Package),
+        ":PACKAGE_WITH_PROTOCOL:" => Some(Predefined::PackageWithProtocol),
+        ":PATH:" => Some(Predefined::Path),
+        ":URL:" => Some(Predefined::Url),
+        _ if pattern.starts_with(':') && pattern.ends_with(':') => {
+            bail!("unknown predefined group {}", pattern)
+        }
+        _ => None,
+    };
+    let matcher = match predefined {
+        Some(p) => SourceMatcher::Predefined(p),
+        None => SourceMatcher::Glob(glob(pattern)?),
+    };
+    Ok((negated, matcher))
+}
+
+fn glob(pattern: &str) -> Result<GlobMatcher> {
+    Ok(GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .with_context(|| format!("invalid group pattern {}", pattern))?
+        .compile_matcher())
+}
+
+impl SourceMatcher {
+    fn is_match(&self, source: &str) -> bool {
+        match self {
+            SourceMatcher::Glob(glob) => glob.is_match(source),
+            SourceMatcher::Predefined(p) => p.is_match(source),
+        }
+    }
+}
+
+impl Predefined {
+    fn is_match(self, source: &str) -> bool {
+        match self {
+            Predefined::Alias => ["@/", "#", "~", "$", "%"]
+                .iter()
+                .any(|prefix| source.starts_with(prefix)),
+            Predefined::Bun => source == "bun" || source.starts_with("bun:"),
+            Predefined::Node => {
+                source.starts_with("node:") || {
+                    let name = source.split('/').next().unwrap_or(source);
+                    NODE_BUILTINS.contains(&name)
+                }
+            }
+            Predefined::Package => {
+                !Predefined::Alias.is_match(source)
+                    && !Predefined::Bun.is_match(source)
+                    && !Predefined::Node.is_match(source)
+                    && !Predefined::Path.is_match(source)
+                    && !source.contains(':')
+            }
+            Predefined::PackageWithProtocol => {
+                !Predefined::Url.is_match(source)
+                    && !Predefined::Bun.is_match(source)
+                    && !source.starts_with("node:")
+                    && source.contains(':')
+            }
+            Predefined::Path => {
+                source.starts_with('/')
+                    || source.starts_with("./")
+                    || source.starts_with("../")
+                    || source == "."
+                    || source == ".."
+            }
+            Predefined::Url => source.starts_with("http://") || source.starts_with("https://"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_predefined_matchers() {
+        let cases = [
+            (Predefined::Alias, "@/components/button", true),
+            (Predefined::Alias, "~/lib", true),
+            (Predefined::Alias, "@scope/pkg", false),
+            (Predefined::Bun, "bun:test", true),
+            (Predefined::Node, "node:fs", true),
+            (Predefined::Node, "fs/promises", true),
+            (Predefined::Node, "react", false),
+            (Predefined::Package, "react", true),
+            (Predefined::Package, "@scope/pkg/sub", true),
+            (Predefined::Package, "./local", false),
+            (Predefined::Package, "fs", false),
+            (Predefined::PackageWithProtocol, "npm:react", true),
+            (Predefined::PackageWithProtocol, "jsr:@std/path", true),
+            (
+                Predefined::PackageWithProtocol,
+                "https://esm.sh/react",
+                false,
+            ),
+            (Predefined::Path, "../up", true),
+            (Predefined::Path, "/abs", true),
+            (Predefined::Url, "https://esm.sh/react", true),
+        ];
+        for (matcher, source, expected) in cases {
+            assert_eq!(
+                matcher.is_match(source),
+                expected,
+                "{:?} {}",
+                matcher,
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_blank_line_between() {
+        let groups = ImportGroups::from_json(&json!([
+            [":BUN:", ":NODE:"],
+            ":BLANK_LINE:",
+            [":PACKAGE:", "!@/**"],
+            ":BLANK_LINE:",
+            ["@/**"],
+            [":PATH:"]
+        ]))
+        .unwrap();
+        assert!(!groups.keeps_no_blank_lines());
+        assert!(groups.blank_line_between("node:fs", "react"));
+        assert!(groups.blank_line_between("react", "node:fs"));
+        assert!(groups.blank_line_between("react", "@/lib"));
+        assert!(!groups.blank_line_between("react", "@scope/pkg"));
+        assert!(!groups.blank_line_between("@/lib", "./local"));
+        // Sources matching no group go last, past no marker here.
+        assert!(!groups.blank_line_between("./local", "https://esm.sh/x"));
+    }
+
+    #[test]
+    fn test_invalid_groups() {
+        assert!(ImportGroups::from_json(&json!(":NODE:")).is_err());
+        assert!(ImportGroups::from_json(&json!([":NOPE:"])).is_err());
+        assert!(ImportGroups::from_json(&json!([[1]])).is_err());
+        assert!(ImportGroups::from_json(&json!([]))
+            .unwrap()
+            .keeps_no_blank_lines());
+    }
+}
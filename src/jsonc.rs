@@ -0,0 +1,131 @@
+//! JSON with comments and trailing commas, as accepted by Biome for both
+//! `biome.json` and `biome.jsonc`.
+
+use anyhow::{anyhow, Result};
+
+/// Parse JSONC. Errors carry the 1-based line and column of the problem.
+pub fn parse(content: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(&strip(content)).map_err(|e| {
+        anyhow!(
+            "{} (line {}, column {})",
+            error_message(&e),
+            e.line(),
+            e.column()
+        )
+    })
+}
+
+/// serde_json's message without its own " at line L column C" suffix.
+fn error_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    match message.rfind(" at line ") {
+        Some(idx) => message[..idx].to_string(),
+        None => message,
+    }
+}
+
+/// Replace comments and trailing commas with whitespace. Line breaks inside
+/// block comments are kept, so positions in the result match the input.
+pub fn strip(content: &str) -> String {
+    let bytes = content.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut i = 0;
+    // Position of the last comma that follows a value, until another value follows.
+    let mut comma: Option<usize> = None;
+    // Last significant byte outside strings and comments.
+    let mut prev: Option<u8> = None;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                comma = None;
+                prev = Some(b'"');
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' && bytes[i] != b'\r' {
+                    out[i] = b' ';
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = content[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+                for b in &mut out[i..end] {
+                    if *b != b'\n' && *b != b'\r' {
+                        *b = b' ';
+                    }
+                }
+                i = end;
+            }
+            b if b.is_ascii_whitespace() => i += 1,
+            b => {
+                match b {
+                    // `[,]` or `{"a": 1,,}` stay invalid.
+                    b',' if !matches!(prev, None | Some(b'{' | b'[' | b',' | b':')) => {
+                        comma = Some(i)
+                    }
+                    b'}' | b']' => {
+                        if let Some(at) = comma.take() {
+                            out[at] = b' ';
+                        }
+                    }
+                    _ => comma = None,
+                }
+                prev = Some(b);
+                i += 1;
+            }
+        }
+    }
+
+    // Only ASCII bytes outside strings were replaced, so this stays valid UTF-8.
+    String::from_utf8(out).expect("stripping keeps UTF-8 intact")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_and_trailing_commas() {
+        let value = parse(
+            r#"{
+  // line comment
+  "files": {
+    /* block
+       comment */
+    "includes": ["src/**", "!**/*.test.ts",],
+  },
+  "url": "http://example.com/*not a comment*/", // trailing
+}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value["files"]["includes"],
+            serde_json::json!(["src/**", "!**/*.test.ts"])
+        );
+        assert_eq!(value["url"], "http://example.com/*not a comment*/");
+    }
+
+    #[test]
+    fn test_strip_keeps_positions() {
+        let input = "{\n  /* a\n  b */ \"x\": 1, // c\n}";
+        let stripped = strip(input);
+        assert_eq!(stripped.len(), input.len());
+        assert_eq!(stripped.lines().count(), input.lines().count());
+        assert_eq!(stripped, "{\n      \n       \"x\": 1      \n}");
+    }
+
+    #[test]
+    fn test_error_has_line_and_column() {
+        let err =
+            parse("{\n  // ok\n  \"files\": {\n    \"includes\": [\"src/**\" \"lib/**\"]\n  }\n}")
+                .unwrap_err();
+        assert_eq!(err.to_string(), "expected `,` or `]` (line 4, column 27)");
+    }
+}
@@ -1,172 +1,350 @@
 use anyhow::Result;
 use std::fs;
+use std::ops::RangeInclusive;
 use std::path::Path;
 
+use groups::ImportGroups;
+use scanner::LineKind;
+
 pub mod config;
+pub mod diff;
+pub mod encoding;
+pub mod git;
+pub mod groups;
+pub mod jsonc;
+pub mod lsp;
+pub mod report;
+pub mod scanner;
+pub mod settings;
+pub mod vcs;
+pub mod watch;
 
 #[derive(Debug, PartialEq)]
 pub enum FileResult {
     /// File was unchanged (already clean)
     Unchanged,
     /// File was modified (or would be modified in check mode)
-    Changed,
+    Changed {
+        /// 1-based line numbers (in the original file) of the removed blank lines
+        removed_lines: Vec<usize>,
+    },
+    /// File was not processed, with the reason (e.g. it could not be decoded)
+    Skipped(String),
 }
 
-/// Determine if a line starts an import statement.
-pub fn is_import_line(line: &str) -> bool {
-    let trimmed = line.trim();
-    if trimmed.starts_with("import ") || trimmed.starts_with("import(") {
-        // Exclude `import.meta.glob` — handled separately
-        if trimmed.starts_with("import.meta") {
-            return false;
-        }
-        return true;
-    }
-    if trimmed == "import" {
-        return true;
-    }
-    false
+/// Output of [`squeeze`]: the new content and which lines were removed.
+#[derive(Debug, PartialEq)]
+pub struct Squeezed {
+    pub output: String,
+    /// 1-based line numbers (in the original content) of the removed blank lines
+    pub removed_lines: Vec<usize>,
 }
 
-/// Determine if a line is part of an import.meta expression (single or multiline).
-fn is_import_meta_line(line: &str) -> bool {
-    let trimmed = line.trim();
-    trimmed.starts_with("import.meta")
+/// Determine if a line starts an import statement.
+///
+/// Classifies the line on its own with [`scanner::scan`]; prefer scanning whole
+/// files, which also tells imports inside strings and comments apart.
+pub fn is_import_line(line: &str) -> bool {
+    // `import.meta` expressions are squeezed along with imports, but don't start one.
+    !line.trim_start().starts_with("import.meta")
+        && scanner::scan(line)
+            .imports
+            .first()
+            .is_some_and(|import| import.start_line == 0)
 }
 
 /// Track whether we are inside a multiline construct (import or import.meta).
 /// Returns the new `in_multiline` state.
+///
+/// The lines before are not available, so an open `import {` stands in for them;
+/// the statement is still open if a line after `line` would belong to it.
 pub fn is_in_multiline_import(line: &str, in_multiline: bool) -> bool {
-    if in_multiline {
-        // We're inside a multiline import/expression.
-        // Check if this line closes it.
-        let trimmed = line.trim();
-        // Count braces/parens to detect closure (simple heuristic)
-        if trimmed.contains('}') || trimmed.ends_with(')') || trimmed.ends_with(");") {
-            return false;
-        }
-        return true;
-    }
-
-    // Not currently in multiline — check if this line opens one
-    let trimmed = line.trim();
-
-    // Multiline import: has `{` but no `}` on same line
-    if is_import_line(trimmed) && trimmed.contains('{') && !trimmed.contains('}') {
-        return true;
-    }
+    let opener = if in_multiline { "import {\n" } else { "" };
+    let scan = scanner::scan(&format!("{}{}\nx", opener, line));
+    scan.lines.last() == Some(&LineKind::Import)
+}
 
-    // import.meta.glob(...) multiline: has `(` but no `)` on same line
-    if is_import_meta_line(trimmed) && trimmed.contains('(') && !trimmed.contains(')') {
-        return true;
-    }
+/// Core transform: remove blank lines between import statements.
+/// Pure function — no I/O.
+///
+/// Lines are classified by [`scanner::scan`], so imports inside strings, template
+/// literals, comments or nested blocks are never mistaken for real ones.
+/// Comments between imports are kept; blank lines between imports are dropped.
+/// Only whole lines are ever removed, so every remaining line keeps its original
+/// terminator (`\r\n`, `\n` or `\r`).
+pub fn squeeze_imports(content: &str) -> String {
+    squeeze(content).output
+}
 
-    false
+/// Like [`squeeze_imports`], but also reports the line numbers that were removed.
+pub fn squeeze(content: &str) -> Squeezed {
+    squeeze_with(content, &SqueezeOptions::default())
 }
 
-/// Returns true if line is a comment (single-line or block comment start/end).
-fn is_comment_line(line: &str) -> bool {
-    let trimmed = line.trim();
-    trimmed.starts_with("//") || trimmed.starts_with("/*") || trimmed.starts_with("*") || trimmed.ends_with("*/")
+/// Options for [`squeeze_with`].
+#[derive(Debug, Clone, Default)]
+pub struct SqueezeOptions {
+    /// Only squeeze import blocks that overlap one of these 1-based, inclusive
+    /// line ranges. `None` squeezes every block.
+    pub line_ranges: Option<Vec<RangeInclusive<usize>>>,
+    /// Biome's organizeImports groups: one blank line is kept between two
+    /// imports that Biome separates with a `:BLANK_LINE:`.
+    pub groups: ImportGroups,
+    /// Skip content larger than this many bytes, like Biome's `files.maxSize`.
+    pub max_size: Option<u64>,
 }
 
-/// Core transform: remove blank lines between import statements.
-/// Pure function — no I/O.
-pub fn squeeze_imports(content: &str) -> String {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut result: Vec<&str> = Vec::with_capacity(lines.len());
-    let mut in_multiline = false;
-    let mut in_import_block = false;
-    let mut pending_blank_lines: Vec<&str> = Vec::new();
-    let mut pending_comment_lines: Vec<&str> = Vec::new();
-
-    for line in &lines {
-        let trimmed = line.trim();
-        let is_blank = trimmed.is_empty();
-        let is_comment = is_comment_line(trimmed);
-        let is_import = is_import_line(trimmed) || is_import_meta_line(trimmed) || in_multiline;
-
-        if in_multiline {
-            // Continue multiline import — always include
-            result.push(line);
-            in_multiline = is_in_multiline_import(line, true);
-            continue;
-        }
+/// Content whose lines average more bytes than this is taken for minified.
+const MINIFIED_LINE_LENGTH: usize = 500;
+
+impl SqueezeOptions {
+    /// Why content of `size` bytes is skipped, if it is too large.
+    fn size_limit_reason(&self, size: u64) -> Option<String> {
+        let max_size = self.max_size?;
+        (size > max_size).then(|| {
+            format!(
+                "file is larger than files.maxSize ({} > {} bytes)",
+                size, max_size
+            )
+        })
+    }
 
-        if is_import {
-            in_import_block = true;
-            // We hit an import line — discard any pending blank lines
-            // but keep comment lines that were between imports
-            // Actually: discard blank lines between imports, keep comments
-            // Re-think: we discard blank lines between imports and also between
-            // comments that are sandwiched between imports.
-            // Flush pending comments (they are between imports)
-            for cl in pending_comment_lines.drain(..) {
-                result.push(cl);
-            }
-            pending_blank_lines.clear();
-            result.push(line);
-            in_multiline = is_in_multiline_import(line, false);
-            continue;
+    fn allows_block(&self, first: usize, last: usize) -> bool {
+        match &self.line_ranges {
+            Some(ranges) => ranges
+                .iter()
+                .any(|r| *r.start() <= last && first <= *r.end()),
+            None => true,
         }
+    }
+}
 
-        if in_import_block {
-            if is_blank {
-                pending_blank_lines.push(line);
-                continue;
+/// Like [`squeeze`], with [`SqueezeOptions`].
+///
+/// An import block runs from an import to the last import that follows it with
+/// only blank lines and comments in between.
+pub fn squeeze_with(content: &str, options: &SqueezeOptions) -> Squeezed {
+    let scan = scanner::scan(content);
+    let lines: Vec<&str> = split_lines_inclusive(content).collect();
+    let mut removed = vec![false; lines.len()];
+
+    // Module specifier of the import each line belongs to, if any.
+    let mut sources: Vec<Option<&str>> = vec![None; lines.len()];
+    for import in &scan.imports {
+        for source in sources
+            .iter_mut()
+            .take(import.end_line + 1)
+            .skip(import.start_line)
+        {
+            *source = import.source.as_deref();
+        }
+    }
+    // Specifier of the latest import in the current block.
+    let mut last_source: Option<&str> = None;
+
+    // 0-based line of the first import in the current block, and of the latest.
+    let mut block: Option<(usize, usize)> = None;
+    // Blank lines between imports of the current block, and since its last import.
+    let mut block_blanks: Vec<usize> = Vec::new();
+    let mut pending_blanks: Vec<usize> = Vec::new();
+
+    let mut finish_block = |block: Option<(usize, usize)>, blanks: &mut Vec<usize>| {
+        if let Some((first, last)) = block {
+            if options.allows_block(first + 1, last + 1) {
+                for &idx in blanks.iter() {
+                    removed[idx] = true;
+                }
             }
-            if is_comment {
-                pending_comment_lines.push(line);
-                continue;
+        }
+        blanks.clear();
+    };
+
+    for (idx, kind) in scan.lines.iter().enumerate().take(lines.len()) {
+        match (kind, block) {
+            (LineKind::Import, Some((first, _))) => {
+                // Blank lines between imports are dropped, except the first one
+                // between two groups Biome keeps apart; comments sandwiched
+                // between imports are kept in place.
+                if let (Some(a), Some(b)) = (last_source, sources[idx]) {
+                    if !pending_blanks.is_empty() && options.groups.blank_line_between(a, b) {
+                        pending_blanks.remove(0);
+                    }
+                }
+                block_blanks.append(&mut pending_blanks);
+                block = Some((first, idx));
+                last_source = sources[idx];
             }
-            // Non-import, non-blank, non-comment line while we were in import block
-            // => import block ended. Flush pending blanks and comments.
-            in_import_block = false;
-            for bl in pending_blank_lines.drain(..) {
-                result.push(bl);
+            (LineKind::Import, None) => {
+                block = Some((idx, idx));
+                last_source = sources[idx];
             }
-            for cl in pending_comment_lines.drain(..) {
-                result.push(cl);
+            (LineKind::Blank, Some(_)) => pending_blanks.push(idx),
+            (LineKind::Comment, Some(_)) => {}
+            _ => {
+                // Import block ended (or never started); trailing blanks stay.
+                finish_block(block.take(), &mut block_blanks);
+                pending_blanks.clear();
             }
-            result.push(line);
-            continue;
         }
-
-        // Not in import block — pass through
-        result.push(line);
     }
-
-    // Flush any remaining pending lines
-    for bl in pending_blank_lines.drain(..) {
-        result.push(bl);
-    }
-    for cl in pending_comment_lines.drain(..) {
-        result.push(cl);
+    finish_block(block, &mut block_blanks);
+
+    let mut output = String::with_capacity(content.len());
+    let mut removed_lines = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if removed[idx] {
+            removed_lines.push(idx + 1);
+        } else {
+            output.push_str(line);
+        }
     }
 
-    let mut output = result.join("\n");
-    // Preserve trailing newline if original had one
-    if content.ends_with('\n') {
-        output.push('\n');
+    Squeezed {
+        output,
+        removed_lines,
     }
-    output
+}
+
+/// Whether `content` looks like minified code: its lines are very long on
+/// average. Squeezing bundles is pointless, and Biome doesn't sort them either.
+fn is_minified(content: &str) -> bool {
+    let lines = split_lines_inclusive(content).count().max(1);
+    content.len() / lines > MINIFIED_LINE_LENGTH
+}
+
+/// Split `content` into lines, each including its terminator (`\r\n`, `\n` or
+/// a lone `\r`). The last line has no terminator if the file doesn't end with one.
+pub(crate) fn split_lines_inclusive(content: &str) -> impl Iterator<Item = &str> {
+    let bytes = content.as_bytes();
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        if start >= bytes.len() {
+            return None;
+        }
+        let mut i = start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' => {
+                    i += 1;
+                    break;
+                }
+                b'\r' => {
+                    i += if bytes.get(i + 1) == Some(&b'\n') {
+                        2
+                    } else {
+                        1
+                    };
+                    break;
+                }
+                _ => i += 1,
+            }
+        }
+        let line = &content[start..i];
+        start = i;
+        Some(line)
+    })
 }
 
 /// Process a single file. Returns whether the file was changed.
 /// In check mode, does not write to disk.
+///
+/// The file's encoding (UTF-8 with or without BOM, UTF-16) is preserved on write.
+/// Files that cannot be decoded are reported as [`FileResult::Skipped`].
 pub fn process_file(path: &Path, check: bool) -> Result<FileResult> {
-    let content = fs::read_to_string(path)?;
-    let squeezed = squeeze_imports(&content);
+    process_file_with_options(path, check, None, &SqueezeOptions::default())
+        .map(|(result, _)| result)
+}
+
+/// Like [`process_file`], but with `diff_color: Some(color)` also returns a
+/// unified diff of the change when the file was (or would be) modified.
+pub fn process_file_with_diff(
+    path: &Path,
+    check: bool,
+    diff_color: Option<bool>,
+) -> Result<(FileResult, Option<String>)> {
+    process_file_with_options(path, check, diff_color, &SqueezeOptions::default())
+}
+
+/// Outcome of [`process_bytes`].
+#[derive(Debug, PartialEq)]
+pub struct Processed {
+    pub result: FileResult,
+    /// The squeezed content in the input's original encoding, if it changed.
+    pub output: Option<Vec<u8>>,
+    /// Unified diff of the change, if one was requested and the content changed.
+    pub diff: Option<String>,
+}
+
+/// Content-in/content-out counterpart of [`process_file`]: decode `bytes`, squeeze
+/// them and re-encode the result. `label` names the content in diff headers;
+/// `diff_color` is `Some` when a diff should be rendered.
+pub fn process_bytes(bytes: &[u8], label: &str, diff_color: Option<bool>) -> Processed {
+    process_bytes_with_options(bytes, label, diff_color, &SqueezeOptions::default())
+}
+
+/// Like [`process_bytes`], with [`SqueezeOptions`].
+pub fn process_bytes_with_options(
+    bytes: &[u8],
+    label: &str,
+    diff_color: Option<bool>,
+    options: &SqueezeOptions,
+) -> Processed {
+    let skipped = |reason| Processed {
+        result: FileResult::Skipped(reason),
+        output: None,
+        diff: None,
+    };
+    if let Some(reason) = options.size_limit_reason(bytes.len() as u64) {
+        return skipped(reason);
+    }
+    let (content, encoding) = match encoding::decode(bytes) {
+        Ok(decoded) => decoded,
+        Err(reason) => return skipped(reason),
+    };
+    if is_minified(&content) {
+        return skipped("file looks minified".to_string());
+    }
+    let Squeezed {
+        output: squeezed,
+        removed_lines,
+    } = squeeze_with(&content, options);
+
+    if removed_lines.is_empty() {
+        return Processed {
+            result: FileResult::Unchanged,
+            output: None,
+            diff: None,
+        };
+    }
+
+    Processed {
+        output: Some(encoding::encode(&squeezed, encoding)),
+        diff: diff_color.map(|color| diff::unified_diff(label, &content, &removed_lines, color)),
+        result: FileResult::Changed { removed_lines },
+    }
+}
 
-    if squeezed == content {
-        return Ok(FileResult::Unchanged);
+/// Shared implementation of [`process_file`] and [`process_file_with_diff`];
+/// `diff_color` is `Some` when a diff should be rendered.
+pub fn process_file_with_options(
+    path: &Path,
+    check: bool,
+    diff_color: Option<bool>,
+    options: &SqueezeOptions,
+) -> Result<(FileResult, Option<String>)> {
+    // Don't even read files over the limit.
+    if let Some(reason) = options.size_limit_reason(fs::metadata(path)?.len()) {
+        return Ok((FileResult::Skipped(reason), None));
     }
+    let bytes = fs::read(path)?;
+    let processed =
+        process_bytes_with_options(&bytes, &path.display().to_string(), diff_color, options);
 
-    if !check {
-        fs::write(path, &squeezed)?;
+    if let (Some(output), false) = (&processed.output, check) {
+        fs::write(path, output)?;
     }
 
-    Ok(FileResult::Changed)
+    Ok((processed.result, processed.diff))
 }
 
 #[cfg(test)]
@@ -185,6 +363,17 @@ mod tests {
         assert!(!is_import_line(""));
     }
 
+    #[test]
+    fn test_is_in_multiline_import() {
+        assert!(is_in_multiline_import("import {", false));
+        assert!(is_in_multiline_import("  a,", true));
+        assert!(!is_in_multiline_import("} from 'a'", true));
+        assert!(!is_in_multiline_import("import { a } from 'a'", false));
+        assert!(is_in_multiline_import("import.meta.glob(", false));
+        assert!(!is_in_multiline_import(")", true));
+        assert!(!is_in_multiline_import("const x = {", false));
+    }
+
     #[test]
     fn test_basic_squeeze() {
         let input = "\
@@ -388,4 +577,254 @@ const y = 2
         let expected = "import { a } from 'a'\nimport { b } from 'b'";
         assert_eq!(squeeze_imports(input), expected);
     }
+
+    #[test]
+    fn test_process_bytes() {
+        let processed = process_bytes(b"import a from 'a'\r\n\r\nimport b from 'b'\r\n", "-", None);
+        assert_eq!(
+            processed,
+            Processed {
+                result: FileResult::Changed {
+                    removed_lines: vec![2]
+                },
+                output: Some(b"import a from 'a'\r\nimport b from 'b'\r\n".to_vec()),
+                diff: None,
+            }
+        );
+
+        let processed = process_bytes(b"const x = 1\n", "-", Some(false));
+        assert_eq!(processed.result, FileResult::Unchanged);
+        assert_eq!(processed.output, None);
+    }
+
+    #[test]
+    fn test_process_bytes_skips_large_and_minified() {
+        let input = b"import a from 'a'\n\nimport b from 'b'\n";
+        let options = SqueezeOptions {
+            max_size: Some(16),
+            ..Default::default()
+        };
+        assert_eq!(
+            process_bytes_with_options(input, "-", None, &options).result,
+            FileResult::Skipped("file is larger than files.maxSize (37 > 16 bytes)".to_string())
+        );
+
+        let bundle = format!("import a from 'a';\n\n{}\n", "var x=1;".repeat(200));
+        assert_eq!(
+            process_bytes(bundle.as_bytes(), "-", None).result,
+            FileResult::Skipped("file looks minified".to_string())
+        );
+    }
+
+    #[test]
+    fn test_squeeze_reports_removed_lines() {
+        let input = "\
+import { a } from 'a'
+
+// note
+
+import { b } from 'b'
+
+const x = 1
+";
+        let squeezed = squeeze(input);
+        assert_eq!(squeezed.removed_lines, vec![2, 4]);
+        assert_eq!(squeezed.output, squeeze_imports(input));
+    }
+
+    #[test]
+    fn test_squeeze_with_line_ranges() {
+        let input = "\
+import { a } from 'a'
+
+import { b } from 'b'
+
+const x = 1
+
+import { c } from 'c'
+
+import { d } from 'd'
+";
+        let only = |ranges: Vec<RangeInclusive<usize>>| {
+            let options = SqueezeOptions {
+                line_ranges: Some(ranges),
+                ..Default::default()
+            };
+            squeeze_with(input, &options).removed_lines
+        };
+
+        // Only the block overlapping the range is touched.
+        assert_eq!(only(vec![3..=3]), vec![2]);
+        assert_eq!(only(vec![9..=12]), vec![8]);
+        // The blank line itself counts as part of its block.
+        assert_eq!(only(vec![8..=8]), vec![8]);
+        // Lines between blocks touch neither.
+        assert_eq!(only(vec![4..=6]), Vec::<usize>::new());
+        assert_eq!(only(vec![]), Vec::<usize>::new());
+        assert_eq!(only(vec![1..=1, 7..=7]), vec![2, 8]);
+    }
+
+    #[test]
+    fn test_squeeze_with_groups() {
+        let input = "\
+import fs from 'node:fs'
+
+
+import { a } from 'a'
+
+import { b } from '@/b'
+
+// about c
+
+import { c } from './c'
+";
+        let groups = ImportGroups::from_json(&serde_json::json!([
+            ":NODE:",
+            ":BLANK_LINE:",
+            ":PACKAGE:",
+            ":BLANK_LINE:",
+            [":ALIAS:", ":PATH:"]
+        ]))
+        .unwrap();
+        let options = SqueezeOptions {
+            groups,
+            ..Default::default()
+        };
+        let expected = "\
+import fs from 'node:fs'
+
+import { a } from 'a'
+
+import { b } from '@/b'
+// about c
+import { c } from './c'
+";
+        let squeezed = squeeze_with(input, &options);
+        assert_eq!(squeezed.output, expected);
+        assert_eq!(squeezed.removed_lines, vec![3, 7, 9]);
+        // Squeezing again is a no-op.
+        assert!(squeeze_with(&squeezed.output, &options)
+            .removed_lines
+            .is_empty());
+    }
+
+    #[test]
+    fn test_crlf_preserved() {
+        let input = "import { a } from 'a'\r\n\r\nimport { b } from 'b'\r\n\r\nconst x = 1\r\n";
+        let expected = "import { a } from 'a'\r\nimport { b } from 'b'\r\n\r\nconst x = 1\r\n";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_crlf_already_clean_is_unchanged() {
+        let input = "import { a } from 'a'\r\nimport { b } from 'b'\r\n\r\nconst x = 1\r\n";
+        assert_eq!(squeeze_imports(input), input);
+    }
+
+    #[test]
+    fn test_mixed_line_endings_preserved() {
+        let input = "import { a } from 'a'\r\n\nimport { b } from 'b'\n\r\nimport { c } from 'c'\r\nconst x = 1\n";
+        let expected = "import { a } from 'a'\r\nimport { b } from 'b'\nimport { c } from 'c'\r\nconst x = 1\n";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_lone_cr_line_endings() {
+        let input = "import { a } from 'a'\r\rimport { b } from 'b'\r";
+        let expected = "import { a } from 'a'\rimport { b } from 'b'\r";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_last_blank_line_without_terminator_kept() {
+        let input = "import { a } from 'a'\n\nimport { b } from 'b'\n  ";
+        let expected = "import { a } from 'a'\nimport { b } from 'b'\n  ";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_brace_in_string_inside_multiline_import() {
+        let input = "\
+import {
+  'a}b' as ab,
+  c,
+} from 'x'
+
+import { d } from 'd'
+
+const y = 1
+";
+        let expected = "\
+import {
+  'a}b' as ab,
+  c,
+} from 'x'
+import { d } from 'd'
+
+const y = 1
+";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_block_comment_without_stars_between_imports() {
+        let input = "\
+import { a } from 'a'
+
+/*
+  keep me
+*/
+
+import { b } from 'b'
+";
+        let expected = "\
+import { a } from 'a'
+/*
+  keep me
+*/
+import { b } from 'b'
+";
+        assert_eq!(squeeze_imports(input), expected);
+    }
+
+    #[test]
+    fn test_import_text_in_template_literal_untouched() {
+        let input = "\
+import { a } from 'a'
+
+const s = `
+import { b } from 'b'
+
+import { c } from 'c'
+`
+";
+        assert_eq!(squeeze_imports(input), input);
+    }
+
+    #[test]
+    fn test_import_text_in_jsx_untouched() {
+        let input = "\
+import { a } from 'a'
+
+export const App = () => (
+  <p>
+    import this
+
+    import that
+  </p>
+)
+";
+        assert_eq!(squeeze_imports(input), input);
+    }
+
+    #[test]
+    fn test_comment_after_import_block_keeps_order() {
+        let input = "\
+import { a } from 'a'
+// trailing note
+
+const x = 1
+";
+        assert_eq!(squeeze_imports(input), input);
+    }
 }
@@ -0,0 +1,826 @@
+//! Language Server Protocol server (`import-squeeze lsp`).
+//!
+//! Speaks JSON-RPC over any reader/writer pair (stdio in practice). Publishes a
+//! diagnostic for every blank line `squeeze` would remove, offers a quick fix and
+//! a `source.squeezeImports` action, and implements document and range
+//! formatting. Documents are synced in full.
+//!
+//! Each document is squeezed according to the biome.json that applies to its
+//! file, looked up when it is opened: import groups keep their blank lines, and
+//! documents Biome doesn't organize imports for are left alone.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::config::{self, Matcher};
+use crate::report::RULE_ID;
+use crate::settings;
+use crate::{squeeze_with, SqueezeOptions};
+
+/// Code action kind for the "Squeeze imports" source action.
+pub const SOURCE_ACTION_KIND: &str = "source.squeezeImports";
+
+const ACTION_TITLE: &str = "Squeeze imports";
+const DIAGNOSTIC_MESSAGE: &str = "Blank line between import statements";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+
+/// Run the server until the client sends `exit` or closes the input. Fails on
+/// an `exit` without a prior `shutdown`, so the process exits with code 1 as the
+/// protocol requires.
+pub fn run(reader: impl BufRead, writer: impl Write) -> Result<()> {
+    Server {
+        reader,
+        writer,
+        documents: HashMap::new(),
+        shutdown: false,
+    }
+    .serve()
+}
+
+struct Server<R, W> {
+    reader: R,
+    writer: W,
+    /// Open documents by URI.
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+struct Document {
+    text: String,
+    /// How the document is squeezed; `None` if Biome doesn't organize its
+    /// imports.
+    options: Option<SqueezeOptions>,
+}
+
+impl Document {
+    /// 0-based indices of the lines squeezing would remove.
+    fn removed_lines(&self) -> Vec<usize> {
+        let Some(options) = &self.options else {
+            return Vec::new();
+        };
+        squeeze_with(&self.text, options)
+            .removed_lines
+            .into_iter()
+            .map(|line| line - 1)
+            .collect()
+    }
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    fn serve(&mut self) -> Result<()> {
+        while let Some(body) = read_body(&mut self.reader)? {
+            let message: Value = match serde_json::from_slice(&body) {
+                Ok(message) => message,
+                Err(e) => {
+                    // The id is unknown, so the error goes to no particular request.
+                    let response = json!({
+                        "jsonrpc": "2.0",
+                        "id": null,
+                        "error": { "code": PARSE_ERROR, "message": format!("Invalid JSON: {}", e) },
+                    });
+                    write_message(&mut self.writer, &response)?;
+                    continue;
+                }
+            };
+            let method = message["method"].as_str().unwrap_or_default().to_string();
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match message.get("id").cloned() {
+                // Request
+                Some(id) if !method.is_empty() => {
+                    let response = match self.handle_request(&method, &params) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err((code, msg)) => json!({
+                            "jsonrpc": "2.0",
+                            "id": id,
+                            "error": { "code": code, "message": msg },
+                        }),
+                    };
+                    write_message(&mut self.writer, &response)?;
+                }
+                // Response to a server-initiated request; we send none.
+                Some(_) => {}
+                // Notification
+                None => {
+                    if method == "exit" {
+                        if !self.shutdown {
+                            bail!("Received exit before shutdown");
+                        }
+                        return Ok(());
+                    }
+                    self.handle_notification(&method, &params)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if self.shutdown && method != "shutdown" {
+            return Err((INVALID_REQUEST, "Server is shutting down".to_string()));
+        }
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 1 },
+                    "documentFormattingProvider": true,
+                    "documentRangeFormattingProvider": true,
+                    "codeActionProvider": {
+                        "codeActionKinds": ["quickfix", SOURCE_ACTION_KIND],
+                    },
+                },
+                "serverInfo": {
+                    "name": "import-squeeze",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/formatting" => {
+                let document = self.document(params)?;
+                Ok(Value::Array(removal_edits(&document.removed_lines(), None)))
+            }
+            "textDocument/rangeFormatting" => {
+                let document = self.document(params)?;
+                let start = params["range"]["start"]["line"].as_u64().unwrap_or(0) as usize;
+                let end = params["range"]["end"]["line"].as_u64().unwrap_or(u64::MAX) as usize;
+                Ok(Value::Array(removal_edits(
+                    &document.removed_lines(),
+                    Some((start, end)),
+                )))
+            }
+            "textDocument/codeAction" => {
+                let uri = uri_of(params)?;
+                let document = self.document(params)?;
+                Ok(code_actions(uri, &document.removed_lines(), params))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("Unhandled method: {}", method))),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Result<()> {
+        match method {
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                let (Some(uri), Some(text)) = (doc["uri"].as_str(), doc["text"].as_str()) else {
+                    return Ok(());
+                };
+                let options = match document_options(uri) {
+                    Ok(options) => options,
+                    Err(e) => {
+                        self.log_warning(&format!("{:#}", e))?;
+                        Some(SqueezeOptions::default())
+                    }
+                };
+                let document = Document {
+                    text: text.to_string(),
+                    options,
+                };
+                self.documents.insert(uri.to_string(), document);
+                self.publish_diagnostics(uri)
+            }
+            "textDocument/didChange" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Ok(());
+                };
+                // Full sync: the last change carries the whole document.
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let Some(document) = self.documents.get_mut(uri) {
+                    if let Some(text) = text {
+                        document.text = text.to_string();
+                        self.publish_diagnostics(uri)?;
+                    }
+                }
+                Ok(())
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Ok(());
+                };
+                self.documents.remove(uri);
+                self.notify(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn document(&self, params: &Value) -> Result<&Document, (i64, String)> {
+        let uri = uri_of(params)?;
+        self.documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("Document not open: {}", uri)))
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) -> Result<()> {
+        let lines = self
+            .documents
+            .get(uri)
+            .map(Document::removed_lines)
+            .unwrap_or_default();
+        let diagnostics = diagnostics(lines);
+        self.notify(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    fn log_warning(&mut self, message: &str) -> Result<()> {
+        self.notify(
+            "window/logMessage",
+            json!({ "type": 2, "message": message }),
+        )
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        write_message(
+            &mut self.writer,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+    }
+}
+
+fn uri_of(params: &Value) -> Result<&str, (i64, String)> {
+    params["textDocument"]["uri"]
+        .as_str()
+        .ok_or_else(|| (INVALID_PARAMS, "Missing textDocument.uri".to_string()))
+}
+
+/// Squeeze options for the document at `uri`, from the biome.json that applies
+/// to its file (a nested one, if there is one between the root config and the
+/// file), with the import-squeeze settings found from there applied. `None`
+/// if the file wouldn't be processed: the config doesn't select it, or disables
+/// organizeImports for it. Documents that aren't files get the defaults.
+fn document_options(uri: &str) -> Result<Option<SqueezeOptions>> {
+    let Some(path) = file_path(uri) else {
+        return Ok(Some(SqueezeOptions::default()));
+    };
+    let dir = path.parent().unwrap_or(&path);
+    let settings = settings::find_settings(dir)?
+        .map(|(settings, _)| settings)
+        .unwrap_or_default();
+    let adjustments = settings.adjustments(&settings::Overrides::default())?;
+    let config_file = settings
+        .biome_config
+        .or_else(|| config::find_biome_config(dir));
+    let Some(config_file) = config_file else {
+        return Ok(adjustments
+            .extensions
+            .matches(&path)
+            .then(SqueezeOptions::default));
+    };
+    let (mut biome_config, base_dir) = config::load_biome_config(&config_file)?;
+    adjustments.apply(&mut biome_config);
+    let matcher = Matcher::new(&biome_config, &base_dir)?;
+    if !config::is_included_with_nested(&matcher, &path)?
+        || !config::is_organize_imports_enabled(&matcher, &path)?
+    {
+        return Ok(None);
+    }
+    Ok(Some(
+        config::nested_squeeze_options(&matcher, &path)?
+            .unwrap_or_else(|| biome_config.squeeze_options()),
+    ))
+}
+
+/// The path of a `file:` URI, percent-decoded.
+fn file_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escaped = (encoded[i] == b'%')
+            .then(|| std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/...` on Windows.
+    match path.strip_prefix('/') {
+        Some(rest) if rest.get(1..2) == Some(":") => Some(PathBuf::from(rest)),
+        Some(_) => Some(PathBuf::from(path)),
+        None => None,
+    }
+}
+
+/// Range covering a whole line, including its terminator.
+fn line_range(line: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": 0 },
+        "end": { "line": line + 1, "character": 0 },
+    })
+}
+
+fn diagnostics(lines: Vec<usize>) -> Vec<Value> {
+    lines
+        .into_iter()
+        .map(|line| {
+            json!({
+                "range": line_range(line),
+                "severity": 2,
+                "source": "import-squeeze",
+                "code": RULE_ID,
+                "message": DIAGNOSTIC_MESSAGE,
+            })
+        })
+        .collect()
+}
+
+/// Text edits deleting the removed lines, optionally limited to an inclusive
+/// range of lines. Consecutive lines are merged into a single edit.
+fn removal_edits(lines: &[usize], within: Option<(usize, usize)>) -> Vec<Value> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        if within.is_some_and(|(start, end)| line < start || line > end) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.1 == line => run.1 = line + 1,
+            _ => runs.push((line, line + 1)),
+        }
+    }
+    runs.into_iter()
+        .map(|(start, end)| {
+            json!({
+                "range": {
+                    "start": { "line": start, "character": 0 },
+                    "end": { "line": end, "character": 0 },
+                },
+                "newText": "",
+            })
+        })
+        .collect()
+}
+
+fn code_actions(uri: &str, lines: &[usize], params: &Value) -> Value {
+    if lines.is_empty() {
+        return json!([]);
+    }
+
+    let only: Option<Vec<&str>> = params["context"]["only"]
+        .as_array()
+        .map(|kinds| kinds.iter().filter_map(Value::as_str).collect());
+    let wants = |kind: &str| {
+        only.as_ref().is_none_or(|only| {
+            only.iter()
+                .any(|k| kind == *k || kind.starts_with(&format!("{}.", k)))
+        })
+    };
+
+    let edit = json!({ "changes": { uri: removal_edits(lines, None) } });
+    let mut actions = Vec::new();
+
+    if wants("quickfix") {
+        let start = params["range"]["start"]["line"].as_u64().unwrap_or(0) as usize;
+        let end = params["range"]["end"]["line"].as_u64().unwrap_or(0) as usize;
+        let ours: Vec<Value> = params["context"]["diagnostics"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|d| d["source"] == "import-squeeze")
+            .cloned()
+            .collect();
+        let requested_line_affected = lines.iter().any(|&l| l >= start && l <= end);
+        if !ours.is_empty() || requested_line_affected {
+            actions.push(json!({
+                "title": ACTION_TITLE,
+                "kind": "quickfix",
+                "diagnostics": ours,
+                "isPreferred": true,
+                "edit": edit,
+            }));
+        }
+    }
+
+    if wants(SOURCE_ACTION_KIND) {
+        actions.push(json!({
+            "title": ACTION_TITLE,
+            "kind": SOURCE_ACTION_KIND,
+            "edit": edit,
+        }));
+    }
+
+    Value::Array(actions)
+}
+
+/// Read the body of one `Content-Length`-framed message. Returns `None` at end
+/// of input.
+fn read_body(reader: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+    let mut content_length: Option<usize> = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse()
+                        .context("Invalid Content-Length header")?,
+                );
+            }
+        }
+    }
+
+    let Some(length) = content_length else {
+        bail!("Missing Content-Length header");
+    };
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const URI: &str = "file:///project/src/app.ts";
+    const SOURCE: &str =
+        "import a from 'a'\n\nimport b from 'b'\n\n\nimport c from 'c'\n\nconst x = 1\n";
+
+    /// Minimal in-process client: queues messages, runs the server to completion
+    /// and collects everything it wrote.
+    struct Client {
+        input: Vec<u8>,
+        next_id: i64,
+    }
+
+    impl Client {
+        fn new() -> Self {
+            let mut client = Client {
+                input: Vec::new(),
+                next_id: 0,
+            };
+            client.request("initialize", json!({ "capabilities": {} }));
+            client.notify("initialized", json!({}));
+            client
+        }
+
+        fn request(&mut self, method: &str, params: Value) -> i64 {
+            self.next_id += 1;
+            let id = self.next_id;
+            write_message(
+                &mut self.input,
+                &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+            )
+            .unwrap();
+            id
+        }
+
+        fn notify(&mut self, method: &str, params: Value) {
+            write_message(
+                &mut self.input,
+                &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+            )
+            .unwrap();
+        }
+
+        fn open(&mut self, text: &str) {
+            self.open_uri(URI, text);
+        }
+
+        fn open_uri(&mut self, uri: &str, text: &str) {
+            self.notify(
+                "textDocument/didOpen",
+                json!({ "textDocument": {
+                    "uri": uri, "languageId": "typescript", "version": 1, "text": text,
+                }}),
+            );
+        }
+
+        /// Queue a message body as is, valid JSON or not.
+        fn raw(&mut self, body: &str) {
+            write!(self.input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        }
+
+        fn finish(mut self) -> Vec<Value> {
+            self.request("shutdown", Value::Null);
+            self.notify("exit", Value::Null);
+            let (result, messages) = self.run();
+            result.unwrap();
+            messages
+        }
+
+        fn run(self) -> (Result<()>, Vec<Value>) {
+            let mut output = Vec::new();
+            let result = run(Cursor::new(self.input), &mut output);
+            let mut reader = Cursor::new(output);
+            let mut messages = Vec::new();
+            while let Some(body) = read_body(&mut reader).unwrap() {
+                messages.push(serde_json::from_slice(&body).unwrap());
+            }
+            (result, messages)
+        }
+    }
+
+    fn response(messages: &[Value], id: i64) -> &Value {
+        messages
+            .iter()
+            .find(|m| m["id"] == id)
+            .unwrap_or_else(|| panic!("no response for request {}", id))
+    }
+
+    fn apply(text: &str, edits: &Value) -> String {
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let mut deleted = vec![false; lines.len()];
+        for edit in edits.as_array().unwrap() {
+            let start = edit["range"]["start"]["line"].as_u64().unwrap() as usize;
+            let end = edit["range"]["end"]["line"].as_u64().unwrap() as usize;
+            deleted[start..end].iter_mut().for_each(|d| *d = true);
+        }
+        lines
+            .iter()
+            .zip(deleted)
+            .filter(|(_, d)| !d)
+            .map(|(l, _)| *l)
+            .collect()
+    }
+
+    #[test]
+    fn test_initialize_capabilities() {
+        let messages = Client::new().finish();
+        let caps = &response(&messages, 1)["result"]["capabilities"];
+        assert_eq!(caps["documentFormattingProvider"], true);
+        assert_eq!(caps["documentRangeFormattingProvider"], true);
+        assert_eq!(
+            caps["codeActionProvider"]["codeActionKinds"][1],
+            SOURCE_ACTION_KIND
+        );
+    }
+
+    #[test]
+    fn test_publishes_diagnostics_on_open_and_change() {
+        let mut client = Client::new();
+        client.open(SOURCE);
+        client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [{ "text": "import a from 'a'\nimport b from 'b'\n" }],
+            }),
+        );
+        let messages = client.finish();
+        let published: Vec<&Value> = messages
+            .iter()
+            .filter(|m| m["method"] == "textDocument/publishDiagnostics")
+            .collect();
+        assert_eq!(published.len(), 2);
+
+        let diagnostics = published[0]["params"]["diagnostics"].as_array().unwrap();
+        let lines: Vec<u64> = diagnostics
+            .iter()
+            .map(|d| d["range"]["start"]["line"].as_u64().unwrap())
+            .collect();
+        assert_eq!(lines, vec![1, 3, 4]);
+        assert_eq!(diagnostics[0]["code"], RULE_ID);
+        assert!(published[1]["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_formatting() {
+        let mut client = Client::new();
+        client.open(SOURCE);
+        let id = client.request(
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": URI }, "options": { "tabSize": 2, "insertSpaces": true } }),
+        );
+        let messages = client.finish();
+        let edits = &response(&messages, id)["result"];
+        assert_eq!(edits.as_array().unwrap().len(), 2);
+        assert_eq!(
+            apply(SOURCE, edits),
+            "import a from 'a'\nimport b from 'b'\nimport c from 'c'\n\nconst x = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_range_formatting() {
+        let mut client = Client::new();
+        client.open(SOURCE);
+        let id = client.request(
+            "textDocument/rangeFormatting",
+            json!({
+                "textDocument": { "uri": URI },
+                "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 2, "character": 0 } },
+                "options": { "tabSize": 2, "insertSpaces": true },
+            }),
+        );
+        let messages = client.finish();
+        let edits = &response(&messages, id)["result"];
+        assert_eq!(
+            apply(SOURCE, edits),
+            "import a from 'a'\nimport b from 'b'\n\n\nimport c from 'c'\n\nconst x = 1\n"
+        );
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let mut client = Client::new();
+        client.open(SOURCE);
+        let diagnostic = json!({
+            "range": line_range(1),
+            "source": "import-squeeze",
+            "code": RULE_ID,
+            "message": DIAGNOSTIC_MESSAGE,
+        });
+        let all = client.request(
+            "textDocument/codeAction",
+            json!({
+                "textDocument": { "uri": URI },
+                "range": line_range(1),
+                "context": { "diagnostics": [diagnostic] },
+            }),
+        );
+        let source_only = client.request(
+            "textDocument/codeAction",
+            json!({
+                "textDocument": { "uri": URI },
+                "range": line_range(7),
+                "context": { "diagnostics": [], "only": ["source"] },
+            }),
+        );
+        let messages = client.finish();
+
+        let actions = response(&messages, all)["result"].as_array().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["kind"], "quickfix");
+        assert_eq!(actions[0]["diagnostics"][0]["code"], RULE_ID);
+        assert_eq!(
+            apply(SOURCE, &actions[0]["edit"]["changes"][URI]),
+            crate::squeeze(SOURCE).output
+        );
+
+        let actions = response(&messages, source_only)["result"]
+            .as_array()
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0]["kind"], SOURCE_ACTION_KIND);
+    }
+
+    #[test]
+    fn test_unknown_request_and_unopened_document() {
+        let mut client = Client::new();
+        let unknown = client.request("workspace/symbol", json!({ "query": "" }));
+        let unopened = client.request(
+            "textDocument/formatting",
+            json!({ "textDocument": { "uri": "file:///nope.ts" }, "options": {} }),
+        );
+        let messages = client.finish();
+        assert_eq!(
+            response(&messages, unknown)["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            response(&messages, unopened)["error"]["code"],
+            INVALID_PARAMS
+        );
+    }
+
+    #[test]
+    fn test_documents_follow_biome_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("my project");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("biome.json"),
+            r#"{
+                "assist": { "actions": { "source": { "organizeImports": {
+                    "level": "on",
+                    "options": { "groups": [":NODE:", ":BLANK_LINE:", ":PACKAGE:"] }
+                } } } },
+                "overrides": [{ "includes": ["legacy/**"], "assist": { "enabled": false } }]
+            }"#,
+        )
+        .unwrap();
+        let uri = |rel: &str| format!("file://{}/{}", root.display(), rel).replace(' ', "%20");
+
+        let mut client = Client::new();
+        client.open_uri(
+            &uri("src/app.ts"),
+            "import fs from 'node:fs'\n\nimport a from 'a'\n\nimport b from 'b'\n",
+        );
+        client.open_uri(&uri("legacy/old.ts"), SOURCE);
+        let messages = client.finish();
+        let diagnostic_lines = |rel: &str| -> Vec<u64> {
+            let published = messages
+                .iter()
+                .find(|m| m["params"]["uri"] == uri(rel))
+                .unwrap();
+            published["params"]["diagnostics"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|d| d["range"]["start"]["line"].as_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(diagnostic_lines("src/app.ts"), vec![3]);
+        assert!(diagnostic_lines("legacy/old.ts").is_empty());
+    }
+
+    #[test]
+    fn test_documents_follow_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("biome.json"), "{}").unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"importSqueeze": {"excludes": ["gen/**"], "extensions": ["!cjs"]}}"#,
+        )
+        .unwrap();
+        let uri = |rel: &str| format!("file://{}/{}", root.display(), rel);
+
+        let mut client = Client::new();
+        for rel in ["src/a.ts", "gen/b.ts", "src/c.cjs"] {
+            client.open_uri(&uri(rel), SOURCE);
+        }
+        let messages = client.finish();
+        let diagnostic_count = |rel: &str| {
+            let published = messages
+                .iter()
+                .find(|m| m["params"]["uri"] == uri(rel))
+                .unwrap();
+            published["params"]["diagnostics"].as_array().unwrap().len()
+        };
+        assert_eq!(diagnostic_count("src/a.ts"), 3);
+        assert_eq!(diagnostic_count("gen/b.ts"), 0);
+        assert_eq!(diagnostic_count("src/c.cjs"), 0);
+    }
+
+    #[test]
+    fn test_file_path() {
+        assert_eq!(
+            file_path("file:///home/me/my%20app/a.ts"),
+            Some(PathBuf::from("/home/me/my app/a.ts"))
+        );
+        assert_eq!(
+            file_path("file:///C:/src/a.ts"),
+            Some(PathBuf::from("C:/src/a.ts"))
+        );
+        assert_eq!(file_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    fn test_invalid_json_keeps_serving() {
+        let mut client = Client::new();
+        client.raw("{ not json");
+        let id = client.request("shutdown", Value::Null);
+        client.notify("exit", Value::Null);
+        let (result, messages) = client.run();
+        result.unwrap();
+        let parse_error = messages.iter().find(|m| m["id"].is_null()).unwrap();
+        assert_eq!(parse_error["error"]["code"], PARSE_ERROR);
+        assert_eq!(response(&messages, id)["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_exit_without_shutdown_fails() {
+        let mut client = Client::new();
+        client.notify("exit", Value::Null);
+        let (result, _) = client.run();
+        assert!(result.is_err());
+    }
+}
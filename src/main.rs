@@ -1,13 +1,20 @@
 use anyhow::{bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use rayon::prelude::*;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 
-use import_squeeze::config;
-use import_squeeze::{process_file, FileResult};
+use import_squeeze::report::{self, FileReport, Totals};
+use import_squeeze::{config, git, settings, watch};
+use import_squeeze::{
+    process_bytes_with_options, process_file_with_options, FileResult, SqueezeOptions,
+};
 
 #[derive(Parser, Debug)]
-#[command(name = "import-squeeze", about = "Remove blank lines between import statements")]
+#[command(
+    name = "import-squeeze",
+    about = "Remove blank lines between import statements"
+)]
 struct Cli {
     /// Files to process. If omitted, reads from biome.json.
     files: Vec<PathBuf>,
@@ -23,80 +30,568 @@ struct Cli {
     /// Path to biome.json config file.
     #[arg(long)]
     config: Option<PathBuf>,
+
+    /// Read source from stdin and write the squeezed result to stdout.
+    #[arg(long, conflicts_with = "files")]
+    stdin: bool,
+
+    /// Path of the file being piped through `--stdin`. Used for extension and
+    /// biome.json include/exclude checks; excluded files are passed through unchanged.
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    stdin_filepath: Option<PathBuf>,
+
+    /// Print a unified diff of the blank lines removed from each file.
+    #[arg(long, overrides_with = "no_diff")]
+    diff: bool,
+
+    /// Don't print diffs, even if import-squeeze.json asks for them.
+    #[arg(long, overrides_with = "diff")]
+    no_diff: bool,
+
+    /// When to colorize diff output. `auto` (the default) honors NO_COLOR and
+    /// checks for a terminal.
+    #[arg(long, value_enum)]
+    color: Option<ColorWhen>,
+
+    /// Output format. Defaults to `github` under GitHub Actions, `gitlab` under
+    /// GitLab CI, and `text` otherwise.
+    #[arg(long, value_enum)]
+    reporter: Option<Reporter>,
+
+    /// Run a Language Server Protocol server over stdio instead of processing files.
+    #[arg(long, exclusive = true)]
+    lsp: bool,
+
+    /// Keep running and re-process files as they change. Reloads the config when
+    /// biome.json, a config it extends, a nested biome.json or the settings file
+    /// is edited.
+    #[arg(long, conflicts_with = "stdin")]
+    watch: bool,
+
+    /// Squeeze the staged content of staged files, writing the result to both the
+    /// index and the working tree while keeping unstaged changes.
+    #[arg(long, conflicts_with_all = ["files", "stdin", "watch"])]
+    staged: bool,
+
+    /// Only process files added or modified since the merge base of REF and HEAD
+    /// (committed or not) that biome.json includes.
+    #[arg(
+        long,
+        value_name = "REF",
+        conflicts_with_all = ["files", "stdin", "watch", "staged"]
+    )]
+    changed_since: Option<String>,
+
+    /// Skip files ignored by `.gitignore`, `.ignore` and `.git/info/exclude`
+    /// when walking biome.json's files, even if it doesn't set
+    /// `vcs.useIgnoreFile`.
+    #[arg(long, overrides_with = "no_vcs_use_ignore_file")]
+    vcs_use_ignore_file: bool,
+
+    /// Don't skip ignored files unless biome.json sets `vcs.useIgnoreFile`,
+    /// even if import-squeeze.json asks to.
+    #[arg(long, overrides_with = "vcs_use_ignore_file")]
+    no_vcs_use_ignore_file: bool,
+
+    /// Also process files with extension EXT, or stop processing them with
+    /// `!EXT`. Comma-separated or repeated, e.g. `--ext vue,!cjs`.
+    #[arg(long, value_name = "EXT", value_delimiter = ',')]
+    ext: Vec<String>,
+
+    /// Only squeeze import blocks that overlap lines changed in the working tree
+    /// (against HEAD, or against the `--changed-since` merge base).
+    #[arg(
+        long,
+        conflicts_with_all = ["stdin", "watch", "staged"],
+        overrides_with = "no_changed_lines_only"
+    )]
+    changed_lines_only: bool,
+
+    /// Squeeze whole files, even if import-squeeze.json sets `changedLinesOnly`.
+    #[arg(long, overrides_with = "changed_lines_only")]
+    no_changed_lines_only: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Reporter {
+    /// Human-readable output
+    Text,
+    /// One JSON document per run
+    Json,
+    /// SARIF 2.1.0 log for code scanning
+    Sarif,
+    /// GitHub Actions annotations
+    Github,
+    /// GitLab Code Quality report
+    Gitlab,
+}
+
+impl Reporter {
+    /// Pick a reporter from the CI environment when none was given explicitly.
+    fn detect() -> Self {
+        let is_set = |name: &str| std::env::var(name).is_ok_and(|v| v == "true");
+        if is_set("GITHUB_ACTIONS") {
+            Reporter::Github
+        } else if is_set("GITLAB_CI") {
+            Reporter::Gitlab
+        } else {
+            Reporter::Text
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorWhen {
+    fn enabled(self) -> bool {
+        match self {
+            ColorWhen::Always => true,
+            ColorWhen::Never => false,
+            ColorWhen::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    if cli.lsp {
+        return import_squeeze::lsp::run(std::io::stdin().lock(), std::io::stdout().lock());
+    }
+
+    let cwd = std::env::current_dir()?;
+    let overrides = settings::Overrides {
+        use_ignore_file: if cli.vcs_use_ignore_file {
+            Some(true)
+        } else if cli.no_vcs_use_ignore_file {
+            Some(false)
+        } else {
+            None
+        },
+        extensions: cli.ext.clone(),
+    };
+    let (settings, settings_file) = match settings::find_settings(&cwd)? {
+        Some((settings, file)) => {
+            apply_settings(&mut cli, &settings, &file)?;
+            (settings, Some(file))
+        }
+        None => (settings::Settings::default(), None),
+    };
+    let adjustments = settings.adjustments(&overrides)?;
+
     let check = cli.check;
+    let show_diff = cli.diff;
+    let color = cli.color.unwrap_or(ColorWhen::Auto).enabled();
+    let reporter = cli.reporter.unwrap_or_else(Reporter::detect);
 
-    let files = if !cli.files.is_empty() {
-        cli.files
+    if cli.stdin {
+        return run_stdin(&cli, color, &adjustments);
+    }
+
+    if cli.staged {
+        let biome_config = find_optional_config(cli.config.as_deref(), &cwd, &adjustments)?;
+        let matcher = match &biome_config {
+            // git reports canonical paths; match the config's base dir to them.
+            Some((biome_config, base_dir)) => Some(config::Matcher::new(
+                biome_config,
+                &std::fs::canonicalize(base_dir)?,
+            )?),
+            None => None,
+        };
+        let options = squeeze_options(biome_config.as_ref().map(|(c, _)| c));
+        let staged = git::squeeze_staged(
+            &cwd,
+            check,
+            show_diff.then_some(color),
+            &options,
+            &adjustments.extensions,
+            matcher.as_ref(),
+        )?;
+        for warning in &staged.warnings {
+            eprintln!("Warning: {}", warning);
+        }
+        if staged.reports.is_empty() && reporter == Reporter::Text {
+            eprintln!("No staged files to process.");
+            return Ok(());
+        }
+        let mut reports = staged.reports;
+        relativize(reports.iter_mut().map(|r| &mut r.path), &cwd);
+        emit_report(reporter, &reports, &staged.diffs, check, show_diff, &cwd)?;
+        return exit_status(&reports, check);
+    }
+
+    // Nested configs below the root config's directory bring their own options.
+    let (files, scope, base_options, root) = if !cli.files.is_empty() {
+        // Explicit files aren't filtered by includes, but files Biome never
+        // organizes are still left alone.
+        let (files, options, root) =
+            match find_optional_config(cli.config.as_deref(), &cwd, &adjustments)? {
+                Some((biome_config, base_dir)) => {
+                    let matcher = config::Matcher::new(&biome_config, &base_dir)?;
+                    let mut files = Vec::new();
+                    for path in cli.files {
+                        if config::is_organize_imports_enabled(&matcher, &path)? {
+                            files.push(path);
+                        }
+                    }
+                    (files, squeeze_options(Some(&biome_config)), Some(matcher))
+                }
+                None => (cli.files, squeeze_options(None), None),
+            };
+        (files.clone(), watch::Scope::Files(files), options, root)
     } else {
-        resolve_files_from_config(cli.config.as_deref())?
+        let config_file = find_config_file(cli.config.as_deref())?;
+        let (biome_config, base_dir) = load_config(&config_file, &adjustments)?;
+        let files = match &cli.changed_since {
+            Some(reference) => {
+                // git reports canonical paths; match the config's base dir to them.
+                let base_dir = std::fs::canonicalize(&base_dir)?;
+                let mut files = Vec::new();
+                for path in git::changed_since(&cwd, reference)? {
+                    if config::is_included(&biome_config, &base_dir, &path)? {
+                        files.push(path);
+                    }
+                }
+                relativize(&mut files, &cwd);
+                files
+            }
+            None => config::resolve_file_paths(&biome_config, &base_dir)?,
+        };
+        let options = squeeze_options(Some(&biome_config));
+        let root = Some(config::Matcher::new(&biome_config, &base_dir)?);
+        (files, watch::Scope::Config(config_file), options, root)
     };
 
-    if files.is_empty() {
+    if files.is_empty() && reporter == Reporter::Text && !cli.watch {
         eprintln!("No files to process.");
         return Ok(());
     }
 
-    let results: Vec<(PathBuf, Result<FileResult>)> = files
+    let changed_lines = if cli.changed_lines_only {
+        Some(git::changed_lines(&cwd, cli.changed_since.as_deref())?)
+    } else {
+        None
+    };
+
+    let results: Vec<_> = files
         .into_par_iter()
         .map(|path| {
-            let result = process_file(&path, check);
+            let nested = match &root {
+                Some(root) => config::nested_squeeze_options(root, &path),
+                None => Ok(None),
+            };
+            let result = nested.and_then(|nested| {
+                let options = SqueezeOptions {
+                    line_ranges: changed_lines.as_ref().and_then(|changed| {
+                        let path = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                        changed.for_file(&path)
+                    }),
+                    ..nested.unwrap_or_else(|| base_options.clone())
+                };
+                process_file_with_options(&path, check, show_diff.then_some(color), &options)
+            });
             (path, result)
         })
         .collect();
 
-    let mut changed_count = 0;
-    let mut error_count = 0;
+    let mut reports = Vec::with_capacity(results.len());
+    let mut diffs = Vec::with_capacity(results.len());
+    for (path, result) in results {
+        let (result, diff) = match result {
+            Ok((result, diff)) => (Ok(result), diff),
+            Err(e) => (Err(e.to_string()), None),
+        };
+        reports.push(FileReport { path, result });
+        diffs.push(diff);
+    }
+
+    emit_report(reporter, &reports, &diffs, check, show_diff, &cwd)?;
+
+    if cli.watch {
+        eprintln!("Watching for changes...");
+        let options = watch::Options {
+            check,
+            diff_color: show_diff.then_some(color),
+            debounce: watch::DEBOUNCE,
+            squeeze: base_options,
+            settings_file,
+            overrides,
+        };
+        return watch::run(scope, options, |mut reports, diffs| {
+            relativize(reports.iter_mut().map(|r| &mut r.path), &cwd);
+            if let Err(e) = emit_report(reporter, &reports, &diffs, check, show_diff, &cwd) {
+                eprintln!("Error writing report: {:#}", e);
+            }
+        });
+    }
+
+    exit_status(&reports, check)
+}
+
+/// Fill in options not given on the command line from import-squeeze's own
+/// settings (read from `file`). Their changes to biome.json are made by
+/// [`settings::Settings::adjustments`].
+fn apply_settings(cli: &mut Cli, settings: &settings::Settings, file: &Path) -> Result<()> {
+    // `--no-…` flags turn off what the settings turn on.
+    cli.diff |= settings.diff && !cli.no_diff;
+    // Line ranges only apply when squeezing files from disk.
+    cli.changed_lines_only |= settings.changed_lines_only
+        && !cli.no_changed_lines_only
+        && !(cli.stdin || cli.staged || cli.watch);
+    if cli.config.is_none() {
+        cli.config = settings.biome_config.clone();
+    }
+    if cli.reporter.is_none() {
+        cli.reporter = parse_setting(settings.reporter.as_deref(), "reporter", file)?;
+    }
+    if cli.color.is_none() {
+        cli.color = parse_setting(settings.color.as_deref(), "color", file)?;
+    }
+    Ok(())
+}
+
+/// Parse a setting the way its command-line flag would be parsed.
+fn parse_setting<T: ValueEnum>(value: Option<&str>, name: &str, file: &Path) -> Result<Option<T>> {
+    value
+        .map(|value| {
+            T::from_str(value, false).map_err(|_| {
+                anyhow::anyhow!("Invalid {} \"{}\" in {}", name, value, file.display())
+            })
+        })
+        .transpose()
+}
 
-    for (path, result) in &results {
-        match result {
-            Ok(FileResult::Changed) => {
-                changed_count += 1;
-                if check {
-                    println!("{}", path.display());
+/// Fail the run if `--check` found files to squeeze or any file had an error.
+fn exit_status(reports: &[FileReport], check: bool) -> Result<()> {
+    let totals = Totals::from_reports(reports);
+    if check && totals.changed > 0 {
+        bail!("Check failed: files need import squeezing.");
+    }
+    if totals.errors > 0 {
+        bail!("{} file(s) had errors.", totals.errors);
+    }
+
+    Ok(())
+}
+
+/// Show absolute paths (from git or the watcher) relative to the current directory.
+fn relativize<'a>(paths: impl IntoIterator<Item = &'a mut PathBuf>, cwd: &Path) {
+    let canonical_cwd = std::fs::canonicalize(cwd).unwrap_or_else(|_| cwd.to_path_buf());
+    for path in paths {
+        if let Ok(rel) = path.strip_prefix(&canonical_cwd) {
+            *path = rel.to_path_buf();
+        }
+    }
+}
+
+fn emit_report(
+    reporter: Reporter,
+    reports: &[FileReport],
+    diffs: &[Option<String>],
+    check: bool,
+    show_diff: bool,
+    base_dir: &Path,
+) -> Result<()> {
+    match reporter {
+        Reporter::Text => print_text_report(reports, diffs, check, show_diff),
+        Reporter::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report::json_report(reports, check))?
+        ),
+        Reporter::Sarif => println!(
+            "{}",
+            serde_json::to_string_pretty(&report::sarif_report(reports, base_dir))?
+        ),
+        Reporter::Github => {
+            print!("{}", report::github_annotations(reports, base_dir, check));
+            print_summary(&Totals::from_reports(reports), check);
+        }
+        Reporter::Gitlab => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report::gitlab_code_quality(reports, base_dir))?
+            );
+            print_summary(&Totals::from_reports(reports), check);
+        }
+    }
+    Ok(())
+}
+
+fn print_text_report(
+    reports: &[FileReport],
+    diffs: &[Option<String>],
+    check: bool,
+    show_diff: bool,
+) {
+    for (report, diff) in reports.iter().zip(diffs) {
+        let path = report.path.display();
+        match &report.result {
+            Ok(FileResult::Changed { .. }) => {
+                if show_diff {
+                    print!("{}", diff.as_deref().unwrap_or_default());
+                } else if check {
+                    println!("{}", path);
                 }
             }
             Ok(FileResult::Unchanged) => {}
-            Err(e) => {
-                error_count += 1;
-                eprintln!("Error processing {}: {}", path.display(), e);
-            }
+            Ok(FileResult::Skipped(reason)) => eprintln!("Skipped {}: {}", path, reason),
+            Err(e) => eprintln!("Error processing {}: {}", path, e),
         }
     }
 
-    if check {
-        if changed_count > 0 {
-            eprintln!("{} file(s) would be modified.", changed_count);
-            bail!("Check failed: files need import squeezing.");
+    print_summary(&Totals::from_reports(reports), check);
+}
+
+/// Human-readable run summary on stderr.
+fn print_summary(totals: &Totals, check: bool) {
+    if totals.skipped > 0 {
+        eprintln!("{} file(s) skipped.", totals.skipped);
+    }
+    if totals.changed > 0 {
+        if check {
+            eprintln!("{} file(s) would be modified.", totals.changed);
+        } else {
+            eprintln!("{} file(s) modified.", totals.changed);
         }
-    } else if changed_count > 0 {
-        eprintln!("{} file(s) modified.", changed_count);
     }
+}
+
+/// `--stdin`: squeeze source from stdin and write the result to stdout.
+/// With `--check`, nothing is written and the exit code reports whether the input
+/// needs squeezing; with `--diff`, the diff is written instead of the source.
+fn run_stdin(cli: &Cli, color: bool, adjustments: &config::Adjustments) -> Result<()> {
+    let mut input = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut input)
+        .context("Failed to read stdin")?;
+
+    let label = cli
+        .stdin_filepath
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<stdin>".to_string());
+
+    let (included, options) = match &cli.stdin_filepath {
+        Some(path) => stdin_path_config(path, cli.config.as_deref(), adjustments)?,
+        None => {
+            let cwd = std::env::current_dir()?;
+            let biome_config = find_optional_config(cli.config.as_deref(), &cwd, adjustments)?;
+            (true, squeeze_options(biome_config.as_ref().map(|(c, _)| c)))
+        }
+    };
+
+    let processed = if included {
+        process_bytes_with_options(&input, &label, cli.diff.then_some(color), &options)
+    } else {
+        // Not ours to touch: pass the input through unchanged.
+        import_squeeze::Processed {
+            result: FileResult::Unchanged,
+            output: None,
+            diff: None,
+        }
+    };
 
-    if error_count > 0 {
-        bail!("{} file(s) had errors.", error_count);
+    if let FileResult::Skipped(reason) = &processed.result {
+        eprintln!("Skipped {}: {}", label, reason);
     }
 
+    let mut stdout = std::io::stdout().lock();
+    if cli.diff {
+        stdout.write_all(processed.diff.unwrap_or_default().as_bytes())?;
+    } else if !cli.check {
+        stdout.write_all(processed.output.as_deref().unwrap_or(&input))?;
+    }
+    stdout.flush()?;
+
+    if cli.check && matches!(processed.result, FileResult::Changed { .. }) {
+        bail!("Check failed: {} needs import squeezing.", label);
+    }
     Ok(())
 }
 
-fn resolve_files_from_config(config_path: Option<&std::path::Path>) -> Result<Vec<PathBuf>> {
+/// Whether `--stdin-filepath` would be processed: its extension must be supported
+/// and, if a biome.json applies (from `--config` or found upward from the file),
+/// it must be included by it. Also returns the squeeze options to use, from a
+/// nested config if one applies to the file.
+fn stdin_path_config(
+    path: &Path,
+    config_path: Option<&Path>,
+    adjustments: &config::Adjustments,
+) -> Result<(bool, SqueezeOptions)> {
     let cwd = std::env::current_dir()?;
-
-    let config_file = if let Some(path) = config_path {
-        path.to_path_buf()
-    } else {
-        config::find_biome_config(&cwd)
-            .context("No biome.json found. Provide files as arguments or use --config.")?
+    let config_file = match config_path {
+        Some(p) => Some(p.to_path_buf()),
+        None => cwd.join(path).parent().and_then(config::find_biome_config),
     };
 
-    let content = std::fs::read_to_string(&config_file)
-        .with_context(|| format!("Failed to read {}", config_file.display()))?;
-    let biome_config = config::parse_biome_config(&content)?;
+    match config_file {
+        Some(config_file) => {
+            let (biome_config, base_dir) = load_config(&config_file, adjustments)?;
+            let matcher = config::Matcher::new(&biome_config, &base_dir)?;
+            let included = config::is_included_with_nested(&matcher, path)?;
+            let options = config::nested_squeeze_options(&matcher, path)?
+                .unwrap_or_else(|| squeeze_options(Some(&biome_config)));
+            Ok((included, options))
+        }
+        None => Ok((adjustments.extensions.matches(path), squeeze_options(None))),
+    }
+}
+
+/// Squeeze options taken from a biome.json, or Biome's defaults without one.
+fn squeeze_options(biome_config: Option<&config::BiomeFiles>) -> SqueezeOptions {
+    match biome_config {
+        Some(biome_config) => biome_config.squeeze_options(),
+        None => SqueezeOptions {
+            max_size: Some(config::DEFAULT_MAX_SIZE),
+            ..Default::default()
+        },
+    }
+}
+
+/// Load a biome.json with `adjustments` applied, printing warnings about
+/// settings that were ignored.
+fn load_config(
+    config_file: &Path,
+    adjustments: &config::Adjustments,
+) -> Result<(config::BiomeFiles, PathBuf)> {
+    let (mut biome_config, base_dir) = config::load_biome_config(config_file)?;
+    adjustments.apply(&mut biome_config);
+    for warning in &biome_config.warnings {
+        eprintln!("Warning: {}: {}", config_file.display(), warning);
+    }
+    Ok((biome_config, base_dir))
+}
 
-    let base_dir = config_file.parent().unwrap_or(&cwd);
-    config::resolve_file_paths(&biome_config, base_dir)
+/// The biome.json that applies to explicitly passed files, if any: `--config`
+/// if given, else the nearest one upward from `dir`, with `adjustments` applied.
+fn find_optional_config(
+    config_path: Option<&Path>,
+    dir: &Path,
+    adjustments: &config::Adjustments,
+) -> Result<Option<(config::BiomeFiles, PathBuf)>> {
+    let config_file = match config_path {
+        Some(path) => Some(path.to_path_buf()),
+        None => config::find_biome_config(dir),
+    };
+    config_file
+        .map(|config_file| load_config(&config_file, adjustments))
+        .transpose()
+}
+
+/// The biome.json to use: `--config` if given, else the nearest one upward from
+/// the current directory.
+fn find_config_file(config_path: Option<&Path>) -> Result<PathBuf> {
+    match config_path {
+        Some(path) => Ok(path.to_path_buf()),
+        None => config::find_biome_config(&std::env::current_dir()?)
+            .context("No biome.json found. Provide files as arguments or use --config."),
+    }
 }
@@ -0,0 +1,471 @@
+//! Structured run reports for machine consumption and CI annotations
+//! (`--reporter json|sarif|github|gitlab`).
+
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::FileResult;
+
+/// Version of the JSON report layout. Bumped on breaking changes.
+pub const JSON_REPORT_VERSION: u32 = 1;
+
+/// Rule id used for every finding in SARIF and CI annotations.
+pub const RULE_ID: &str = "blank-line-between-imports";
+
+const RULE_DESCRIPTION: &str = "Blank line between import statements";
+const RULE_HELP: &str = "Biome sorts blank-line-separated import groups independently. \
+Remove blank lines between imports so they are sorted as one block.";
+const INFORMATION_URI: &str = "https://github.com/JiHoon-0330/import-squeeze";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Outcome of processing one file. Errors are kept as their display message.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub result: Result<FileResult, String>,
+}
+
+impl FileReport {
+    /// Removed line numbers, or an empty slice if the file was not changed.
+    pub fn removed_lines(&self) -> &[usize] {
+        match &self.result {
+            Ok(FileResult::Changed { removed_lines }) => removed_lines,
+            _ => &[],
+        }
+    }
+}
+
+/// Run totals across all files.
+#[derive(Debug, Default, PartialEq)]
+pub struct Totals {
+    pub files: usize,
+    pub unchanged: usize,
+    pub changed: usize,
+    pub skipped: usize,
+    pub errors: usize,
+    pub removed_lines: usize,
+}
+
+impl Totals {
+    pub fn from_reports(reports: &[FileReport]) -> Self {
+        let mut totals = Totals {
+            files: reports.len(),
+            ..Totals::default()
+        };
+        for report in reports {
+            match &report.result {
+                Ok(FileResult::Unchanged) => totals.unchanged += 1,
+                Ok(FileResult::Changed { removed_lines }) => {
+                    totals.changed += 1;
+                    totals.removed_lines += removed_lines.len();
+                }
+                Ok(FileResult::Skipped(_)) => totals.skipped += 1,
+                Err(_) => totals.errors += 1,
+            }
+        }
+        totals
+    }
+}
+
+/// Build the JSON report document for a run.
+pub fn json_report(reports: &[FileReport], check: bool) -> Value {
+    let files: Vec<Value> = reports
+        .iter()
+        .map(|report| {
+            let path = report.path.display().to_string();
+            match &report.result {
+                Ok(FileResult::Unchanged) => json!({
+                    "path": path,
+                    "result": "unchanged",
+                    "removedCount": 0,
+                    "removedLines": [],
+                }),
+                Ok(FileResult::Changed { removed_lines }) => json!({
+                    "path": path,
+                    "result": "changed",
+                    "removedCount": removed_lines.len(),
+                    "removedLines": removed_lines,
+                }),
+                Ok(FileResult::Skipped(reason)) => json!({
+                    "path": path,
+                    "result": "skipped",
+                    "reason": reason,
+                    "removedCount": 0,
+                    "removedLines": [],
+                }),
+                Err(message) => json!({
+                    "path": path,
+                    "result": "error",
+                    "message": message,
+                    "removedCount": 0,
+                    "removedLines": [],
+                }),
+            }
+        })
+        .collect();
+
+    let totals = Totals::from_reports(reports);
+    json!({
+        "version": JSON_REPORT_VERSION,
+        "mode": if check { "check" } else { "write" },
+        "files": files,
+        "totals": {
+            "files": totals.files,
+            "unchanged": totals.unchanged,
+            "changed": totals.changed,
+            "skipped": totals.skipped,
+            "errors": totals.errors,
+            "removedLines": totals.removed_lines,
+        },
+    })
+}
+
+/// Build a SARIF 2.1.0 log for a run. Each removed blank line is a result with
+/// a fix that deletes it. Paths under `base_dir` are reported relative to it.
+pub fn sarif_report(reports: &[FileReport], base_dir: &Path) -> Value {
+    let mut results = Vec::new();
+    let mut notifications = Vec::new();
+
+    for report in reports {
+        let artifact = artifact_location(&report.path, base_dir);
+        match &report.result {
+            Ok(FileResult::Changed { removed_lines }) => {
+                for &line in removed_lines {
+                    results.push(json!({
+                        "ruleId": RULE_ID,
+                        "ruleIndex": 0,
+                        "level": "error",
+                        "message": { "text": RULE_DESCRIPTION },
+                        "locations": [{
+                            "physicalLocation": {
+                                "artifactLocation": artifact,
+                                "region": { "startLine": line, "endLine": line },
+                            },
+                        }],
+                        "fixes": [{
+                            "description": { "text": "Remove blank line" },
+                            "artifactChanges": [{
+                                "artifactLocation": artifact,
+                                "replacements": [{
+                                    // The whole line, including its terminator.
+                                    "deletedRegion": {
+                                        "startLine": line,
+                                        "startColumn": 1,
+                                        "endLine": line + 1,
+                                        "endColumn": 1,
+                                    },
+                                }],
+                            }],
+                        }],
+                    }));
+                }
+            }
+            Ok(FileResult::Skipped(reason)) => notifications.push(json!({
+                "level": "note",
+                "message": { "text": format!("Skipped: {}", reason) },
+                "locations": [{ "physicalLocation": { "artifactLocation": artifact } }],
+            })),
+            Err(message) => notifications.push(json!({
+                "level": "error",
+                "message": { "text": message },
+                "locations": [{ "physicalLocation": { "artifactLocation": artifact } }],
+            })),
+            Ok(FileResult::Unchanged) => {}
+        }
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "import-squeeze",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": [{
+                        "id": RULE_ID,
+                        "name": "BlankLineBetweenImports",
+                        "shortDescription": { "text": RULE_DESCRIPTION },
+                        "fullDescription": { "text": RULE_HELP },
+                        "helpUri": INFORMATION_URI,
+                        "defaultConfiguration": { "level": "error" },
+                    }],
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": directory_uri(base_dir) },
+            },
+            "invocations": [{
+                "executionSuccessful": Totals::from_reports(reports).errors == 0,
+                "toolExecutionNotifications": notifications,
+            }],
+            "results": results,
+        }],
+    })
+}
+
+/// GitHub Actions workflow commands: one `::error` annotation per removed blank
+/// line (`::warning` in write mode, where the line has already been removed).
+pub fn github_annotations(reports: &[FileReport], base_dir: &Path, check: bool) -> String {
+    let level = if check { "error" } else { "warning" };
+    let mut out = String::new();
+    for report in reports {
+        let file = github_escape_property(&display_path(&report.path, base_dir));
+        match &report.result {
+            Ok(FileResult::Changed { removed_lines }) => {
+                for line in removed_lines {
+                    out.push_str(&format!(
+                        "::{} file={},line={},title={}::{}\n",
+                        level,
+                        file,
+                        line,
+                        RULE_ID,
+                        github_escape_data(RULE_DESCRIPTION)
+                    ));
+                }
+            }
+            Ok(FileResult::Skipped(reason)) => out.push_str(&format!(
+                "::notice file={}::{}\n",
+                file,
+                github_escape_data(&format!("Skipped: {}", reason))
+            )),
+            Err(message) => out.push_str(&format!(
+                "::error file={}::{}\n",
+                file,
+                github_escape_data(message)
+            )),
+            Ok(FileResult::Unchanged) => {}
+        }
+    }
+    out
+}
+
+fn github_escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_escape_property(s: &str) -> String {
+    github_escape_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// GitLab Code Quality report: a JSON array with one issue per removed blank line.
+pub fn gitlab_code_quality(reports: &[FileReport], base_dir: &Path) -> Value {
+    let mut issues = Vec::new();
+    for report in reports {
+        let path = display_path(&report.path, base_dir);
+        for &line in report.removed_lines() {
+            issues.push(json!({
+                "description": RULE_DESCRIPTION,
+                "check_name": RULE_ID,
+                "fingerprint": fingerprint(&format!("{}:{}:{}", RULE_ID, path, line)),
+                "severity": "major",
+                "location": {
+                    "path": path,
+                    "lines": { "begin": line },
+                },
+            }));
+        }
+    }
+    Value::Array(issues)
+}
+
+/// Stable 64-bit FNV-1a hash, hex encoded. GitLab only needs fingerprints to be
+/// unique and stable across runs, not cryptographic.
+fn fingerprint(key: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in key.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// `path` relative to `base_dir` with `/` separators. Paths outside `base_dir`
+/// are returned as-is.
+pub fn display_path(path: &Path, base_dir: &Path) -> String {
+    let rel = path.strip_prefix(base_dir).unwrap_or(path);
+    let rel = rel.strip_prefix(".").unwrap_or(rel);
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+/// SARIF artifact location: relative to `%SRCROOT%` when possible, otherwise an
+/// absolute `file://` URI.
+fn artifact_location(path: &Path, base_dir: &Path) -> Value {
+    let display = display_path(path, base_dir);
+    if Path::new(&display).is_absolute() {
+        json!({ "uri": directory_uri(Path::new(&display)).trim_end_matches('/') })
+    } else {
+        json!({ "uri": percent_encode(&display), "uriBaseId": "%SRCROOT%" })
+    }
+}
+
+/// `file://` URI for a directory, with a trailing slash as SARIF requires.
+fn directory_uri(dir: &Path) -> String {
+    let mut path = dir.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    if !path.ends_with('/') {
+        path.push('/');
+    }
+    format!("file://{}", percent_encode(&path))
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b'@' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Vec<FileReport> {
+        vec![
+            FileReport {
+                path: PathBuf::from("src/a.ts"),
+                result: Ok(FileResult::Changed {
+                    removed_lines: vec![2, 4],
+                }),
+            },
+            FileReport {
+                path: PathBuf::from("src/b.ts"),
+                result: Ok(FileResult::Unchanged),
+            },
+            FileReport {
+                path: PathBuf::from("src/c.js"),
+                result: Ok(FileResult::Skipped("not valid UTF-8".to_string())),
+            },
+            FileReport {
+                path: PathBuf::from("src/d.ts"),
+                result: Err("permission denied".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_totals() {
+        let totals = Totals::from_reports(&sample());
+        assert_eq!(
+            totals,
+            Totals {
+                files: 4,
+                unchanged: 1,
+                changed: 1,
+                skipped: 1,
+                errors: 1,
+                removed_lines: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_report_shape() {
+        let report = json_report(&sample(), true);
+        assert_eq!(report["version"], 1);
+        assert_eq!(report["mode"], "check");
+        assert_eq!(
+            report["files"][0],
+            json!({
+                "path": "src/a.ts",
+                "result": "changed",
+                "removedCount": 2,
+                "removedLines": [2, 4],
+            })
+        );
+        assert_eq!(report["files"][1]["result"], "unchanged");
+        assert_eq!(report["files"][2]["reason"], "not valid UTF-8");
+        assert_eq!(report["files"][3]["message"], "permission denied");
+        assert_eq!(report["totals"]["removedLines"], 2);
+        assert_eq!(report["totals"]["errors"], 1);
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let report = sarif_report(&sample(), Path::new(""));
+        assert_eq!(report["version"], "2.1.0");
+        let run = &report["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], RULE_ID);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        let location = &results[1]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/a.ts");
+        assert_eq!(location["region"]["startLine"], 4);
+        let deleted =
+            &results[1]["fixes"][0]["artifactChanges"][0]["replacements"][0]["deletedRegion"];
+        assert_eq!(
+            deleted,
+            &json!({ "startLine": 4, "startColumn": 1, "endLine": 5, "endColumn": 1 })
+        );
+
+        let invocation = &run["invocations"][0];
+        assert_eq!(invocation["executionSuccessful"], false);
+        assert_eq!(
+            invocation["toolExecutionNotifications"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_paths_and_uris() {
+        let base = Path::new("/repo");
+        assert_eq!(display_path(Path::new("/repo/src/a.ts"), base), "src/a.ts");
+        assert_eq!(display_path(Path::new("./src/a.ts"), base), "src/a.ts");
+        assert_eq!(
+            artifact_location(Path::new("/repo/src/a b.ts"), base),
+            json!({ "uri": "src/a%20b.ts", "uriBaseId": "%SRCROOT%" })
+        );
+        assert_eq!(
+            artifact_location(Path::new("/other/a.ts"), base),
+            json!({ "uri": "file:///other/a.ts" })
+        );
+        assert_eq!(directory_uri(Path::new("/repo")), "file:///repo/");
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        let out = github_annotations(&sample(), Path::new(""), true);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "::error file=src/a.ts,line=2,title=blank-line-between-imports::Blank line between import statements"
+        );
+        assert_eq!(lines[2], "::notice file=src/c.js::Skipped: not valid UTF-8");
+        assert_eq!(lines[3], "::error file=src/d.ts::permission denied");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn test_github_escaping() {
+        assert_eq!(github_escape_property("a,b:c%"), "a%2Cb%3Ac%25");
+        assert_eq!(github_escape_data("a\nb:c"), "a%0Ab:c");
+    }
+
+    #[test]
+    fn test_gitlab_code_quality() {
+        let report = gitlab_code_quality(&sample(), Path::new(""));
+        let issues = report.as_array().unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0]["check_name"], RULE_ID);
+        assert_eq!(issues[0]["location"]["path"], "src/a.ts");
+        assert_eq!(issues[1]["location"]["lines"]["begin"], 4);
+        assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+    }
+}
@@ -0,0 +1,688 @@
+//! Lightweight JS/TS lexer used to classify source lines.
+//!
+//! This is not a parser. It understands just enough of the language — strings,
+//! template literals, regex literals, comments and bracket depth — to find the
+//! real top-level import statements and to tell blank and comment-only lines
+//! apart from everything else.
+
+/// Classification of a single source line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// Whitespace only, and not inside a string, template literal or comment.
+    Blank,
+    /// Contains only comments (or the inside of a block comment).
+    Comment,
+    /// Part of a top-level import statement, optionally with comments.
+    Import,
+    /// Anything else.
+    Code,
+}
+
+/// A top-level import statement, as an inclusive range of 0-based line indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportStatement {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The module specifier of an import declaration, without quotes.
+    pub source: Option<String>,
+}
+
+/// Result of scanning a source file.
+#[derive(Debug, Default)]
+pub struct Scan {
+    /// One entry per line. Lines end at `\r\n`, `\n` or a lone `\r`; a trailing
+    /// terminator does not start an extra empty line.
+    pub lines: Vec<LineKind>,
+    /// Top-level import statements in source order.
+    pub imports: Vec<ImportStatement>,
+}
+
+/// Scan `source` and classify every line.
+pub fn scan(source: &str) -> Scan {
+    Scanner::new(source).run()
+}
+
+const FLAG_CODE: u8 = 1;
+const FLAG_IMPORT: u8 = 2;
+const FLAG_COMMENT: u8 = 4;
+
+/// Keywords after which an expression (and therefore a regex literal) may follow.
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+    "extends",
+];
+
+/// Punctuation after which a statement cannot end.
+const CONTINUATION_PUNCT: &[u8] = b"=,([{.+-*/%&|^!~?:<>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Brace,
+    Paren,
+    Bracket,
+    TemplateExpr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prev {
+    Start,
+    Punct(u8),
+    Word { keyword: bool },
+    Literal,
+}
+
+impl Prev {
+    fn regex_allowed(self) -> bool {
+        match self {
+            Prev::Start => true,
+            Prev::Punct(c) => c != b')' && c != b']',
+            Prev::Word { keyword } => keyword,
+            Prev::Literal => false,
+        }
+    }
+
+    fn is_continuation(self) -> bool {
+        match self {
+            Prev::Start => false,
+            Prev::Punct(c) => CONTINUATION_PUNCT.contains(&c),
+            Prev::Word { keyword } => keyword,
+            Prev::Literal => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportMode {
+    /// `import x from 'y'`, `import 'y'` — ends after the module specifier.
+    Declaration,
+    /// `import('y')`, `import.meta...`, `import x = require('y')`.
+    Expression,
+}
+
+#[derive(Debug)]
+struct PendingImport {
+    start_line: usize,
+    mode: ImportMode,
+    seen_specifier: bool,
+    source: Option<String>,
+}
+
+struct Scanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+    flags: Vec<u8>,
+    stack: Vec<Frame>,
+    prev: Prev,
+    newline_since_prev: bool,
+    import: Option<PendingImport>,
+    imports: Vec<ImportStatement>,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(source: &'a str) -> Self {
+        Scanner {
+            src: source.as_bytes(),
+            pos: 0,
+            line: 0,
+            flags: vec![0],
+            stack: Vec::new(),
+            prev: Prev::Start,
+            newline_since_prev: false,
+            import: None,
+            imports: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Scan {
+        if let Some(rest) = self.src.strip_prefix("\u{FEFF}".as_bytes()) {
+            self.pos = self.src.len() - rest.len();
+        }
+        if self.src[self.pos..].starts_with(b"#!") {
+            // Hashbang: treat like a line comment.
+            self.mark(FLAG_COMMENT);
+            self.skip_line();
+        }
+
+        while let Some(b) = self.peek(0) {
+            match b {
+                b'\n' | b'\r' => {
+                    self.newline();
+                    self.newline_since_prev = true;
+                }
+                b' ' | b'\t' | 0x0b | 0x0c => self.pos += 1,
+                b'/' if self.peek(1) == Some(b'/') => {
+                    self.mark(FLAG_COMMENT);
+                    self.skip_line();
+                }
+                b'/' if self.peek(1) == Some(b'*') => self.block_comment(),
+                b'/' if self.prev.regex_allowed() => self.regex(),
+                b'\'' | b'"' => self.string(b),
+                b'`' => {
+                    self.pos += 1;
+                    self.template();
+                }
+                b'}' if self.stack.last() == Some(&Frame::TemplateExpr) => {
+                    self.stack.pop();
+                    self.pos += 1;
+                    self.template();
+                }
+                b if is_ident_byte(b) => self.word(),
+                _ => self.punct(b),
+            }
+        }
+
+        self.finish_import();
+
+        // A trailing terminator does not start a final empty line.
+        if self.src.is_empty() || self.src.ends_with(b"\n") || self.src.ends_with(b"\r") {
+            self.flags.pop();
+        }
+
+        let lines = self
+            .flags
+            .iter()
+            .map(|&f| {
+                if f & FLAG_CODE != 0 {
+                    LineKind::Code
+                } else if f & FLAG_IMPORT != 0 {
+                    LineKind::Import
+                } else if f & FLAG_COMMENT != 0 {
+                    LineKind::Comment
+                } else {
+                    LineKind::Blank
+                }
+            })
+            .collect();
+
+        Scan {
+            lines,
+            imports: self.imports,
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    /// Flag for tokens on the current line: import if inside an import statement.
+    fn token_flag(&self) -> u8 {
+        if self.import.is_some() {
+            FLAG_IMPORT
+        } else {
+            FLAG_CODE
+        }
+    }
+
+    fn mark(&mut self, flag: u8) {
+        self.flags[self.line] |= flag;
+    }
+
+    /// Length of the line terminator at `pos`, or 0 if there is none.
+    fn line_break_len(&self) -> usize {
+        match (self.peek(0), self.peek(1)) {
+            (Some(b'\r'), Some(b'\n')) => 2,
+            (Some(b'\n'), _) | (Some(b'\r'), _) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Consume a line terminator that ends the current line.
+    fn newline(&mut self) {
+        if self.stack.is_empty() && self.import_complete_at_newline() {
+            self.finish_import();
+        }
+        self.pos += self.line_break_len();
+        self.line += 1;
+        self.flags.push(0);
+    }
+
+    /// Consume a line terminator inside a multi-line token, marking the new line
+    /// with `flag`.
+    fn newline_in_token(&mut self, flag: u8) {
+        self.pos += self.line_break_len();
+        self.line += 1;
+        self.flags.push(flag);
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(b) = self.peek(0) {
+            if is_line_break(b) {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn block_comment(&mut self) {
+        self.mark(FLAG_COMMENT);
+        self.pos += 2;
+        while let Some(b) = self.peek(0) {
+            if b == b'*' && self.peek(1) == Some(b'/') {
+                self.pos += 2;
+                return;
+            }
+            if is_line_break(b) {
+                self.newline_in_token(FLAG_COMMENT);
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// String literal. Unterminated strings end at the line break so that stray
+    /// quotes (e.g. in JSX text) cannot swallow the rest of the file.
+    fn string(&mut self, quote: u8) {
+        let flag = self.token_flag();
+        self.mark(flag);
+        self.pos += 1;
+        let start = self.pos;
+        while let Some(b) = self.peek(0) {
+            match b {
+                b'\\' => {
+                    if self.peek(1).is_some_and(is_line_break) {
+                        self.pos += 1;
+                        self.newline_in_token(flag);
+                    } else {
+                        self.pos += 2;
+                    }
+                }
+                b'\n' | b'\r' => break,
+                _ if b == quote => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        if self.stack.is_empty() {
+            if let Some(import) = self.import.as_mut() {
+                if !import.seen_specifier && import.mode == ImportMode::Declaration {
+                    let end = if self.src.get(self.pos - 1) == Some(&quote) {
+                        self.pos - 1
+                    } else {
+                        self.pos
+                    };
+                    let text = &self.src[start.min(end)..end];
+                    import.source = Some(String::from_utf8_lossy(text).into_owned());
+                }
+                import.seen_specifier = true;
+            }
+        }
+        self.set_prev(Prev::Literal);
+    }
+
+    /// Template literal text, from just after the opening backtick or a closing
+    /// `}` of a substitution, up to the closing backtick or the next `${`.
+    fn template(&mut self) {
+        let flag = self.token_flag();
+        self.mark(flag);
+        while let Some(b) = self.peek(0) {
+            match b {
+                b'\\' => {
+                    if self.peek(1).is_some_and(is_line_break) {
+                        self.pos += 1;
+                        self.newline_in_token(flag);
+                    } else {
+                        self.pos += 2;
+                    }
+                }
+                b'\n' | b'\r' => self.newline_in_token(flag),
+                b'`' => {
+                    self.pos += 1;
+                    self.set_prev(Prev::Literal);
+                    return;
+                }
+                b'$' if self.peek(1) == Some(b'{') => {
+                    self.pos += 2;
+                    self.stack.push(Frame::TemplateExpr);
+                    self.set_prev(Prev::Punct(b'{'));
+                    return;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.set_prev(Prev::Literal);
+    }
+
+    /// Regex literal, including character classes and trailing flags.
+    fn regex(&mut self) {
+        let flag = self.token_flag();
+        self.mark(flag);
+        self.pos += 1;
+        let mut in_class = false;
+        while let Some(b) = self.peek(0) {
+            match b {
+                b'\\' if !self.peek(1).is_some_and(is_line_break) => self.pos += 2,
+                b'\n' | b'\r' => break,
+                b'[' => {
+                    in_class = true;
+                    self.pos += 1;
+                }
+                b']' => {
+                    in_class = false;
+                    self.pos += 1;
+                }
+                b'/' if !in_class => {
+                    self.pos += 1;
+                    while self.peek(0).is_some_and(is_ident_byte) {
+                        self.pos += 1;
+                    }
+                    break;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.set_prev(Prev::Literal);
+    }
+
+    fn word(&mut self) {
+        let start = self.pos;
+        while self.peek(0).is_some_and(is_ident_byte) {
+            self.pos += 1;
+        }
+        let word = &self.src[start..self.pos];
+
+        if word == b"import" && self.import.is_none() && self.at_statement_start() {
+            let mode = match self.next_significant() {
+                Some(b'(') | Some(b'.') => ImportMode::Expression,
+                _ => ImportMode::Declaration,
+            };
+            self.import = Some(PendingImport {
+                start_line: self.line,
+                mode,
+                seen_specifier: false,
+                source: None,
+            });
+        }
+
+        let flag = self.token_flag();
+        self.mark(flag);
+        let keyword = std::str::from_utf8(word)
+            .map(|w| EXPRESSION_KEYWORDS.contains(&w))
+            .unwrap_or(false);
+        self.set_prev(Prev::Word { keyword });
+    }
+
+    fn punct(&mut self, b: u8) {
+        let flag = self.token_flag();
+        self.mark(flag);
+        self.pos += 1;
+        match b {
+            b'{' => self.stack.push(Frame::Brace),
+            b'(' => self.stack.push(Frame::Paren),
+            b'[' => self.stack.push(Frame::Bracket),
+            b'}' | b')' | b']' => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+        self.set_prev(Prev::Punct(b));
+
+        if self.stack.is_empty() && self.import.is_some() {
+            match b {
+                b';' => self.finish_import(),
+                // `import x = require('y')` is an expression, not a declaration.
+                b'=' => {
+                    if let Some(import) = self.import.as_mut() {
+                        import.mode = ImportMode::Expression;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn set_prev(&mut self, prev: Prev) {
+        self.prev = prev;
+        self.newline_since_prev = false;
+    }
+
+    fn at_statement_start(&self) -> bool {
+        if !self.stack.is_empty() {
+            return false;
+        }
+        match self.prev {
+            Prev::Start | Prev::Punct(b';') | Prev::Punct(b'}') => true,
+            prev => self.newline_since_prev && !prev.is_continuation(),
+        }
+    }
+
+    /// Whether the pending import statement ends at the line break under `pos`.
+    fn import_complete_at_newline(&self) -> bool {
+        let Some(import) = &self.import else {
+            return false;
+        };
+        if self.prev.is_continuation() {
+            return false;
+        }
+        match import.mode {
+            ImportMode::Declaration => {
+                if import.seen_specifier {
+                    matches!(self.prev, Prev::Literal | Prev::Punct(b'}'))
+                } else {
+                    // `import { a }\nfrom 'a'` continues; anything else is malformed
+                    // and should not swallow the lines that follow.
+                    !self.next_word_is(b"from")
+                }
+            }
+            ImportMode::Expression => self.next_significant() != Some(b'.'),
+        }
+    }
+
+    fn finish_import(&mut self) {
+        if let Some(import) = self.import.take() {
+            self.imports.push(ImportStatement {
+                start_line: import.start_line,
+                end_line: self.line,
+                source: import.source,
+            });
+        }
+    }
+
+    fn next_significant_pos(&self) -> usize {
+        let mut i = self.pos;
+        while i < self.src.len() && self.src[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    fn next_significant(&self) -> Option<u8> {
+        self.src.get(self.next_significant_pos()).copied()
+    }
+
+    fn next_word_is(&self, word: &[u8]) -> bool {
+        let i = self.next_significant_pos();
+        self.src[i..].starts_with(word)
+            && !self
+                .src
+                .get(i + word.len())
+                .copied()
+                .is_some_and(is_ident_byte)
+    }
+}
+
+fn is_line_break(b: u8) -> bool {
+    b == b'\n' || b == b'\r'
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<LineKind> {
+        scan(source).lines
+    }
+
+    #[test]
+    fn test_single_line_imports() {
+        assert_eq!(
+            kinds("import { useState } from 'react'"),
+            vec![LineKind::Import]
+        );
+        assert_eq!(
+            kinds("import type { FC } from 'react'"),
+            vec![LineKind::Import]
+        );
+        assert_eq!(kinds("import './styles.css'"), vec![LineKind::Import]);
+        assert_eq!(kinds("  import { foo } from 'bar'"), vec![LineKind::Import]);
+        assert_eq!(
+            kinds("import.meta.glob('./**/*.ts')"),
+            vec![LineKind::Import]
+        );
+        assert_eq!(kinds("const x = 1"), vec![LineKind::Code]);
+        assert_eq!(kinds("// import something"), vec![LineKind::Comment]);
+        assert_eq!(kinds(""), Vec::<LineKind>::new());
+    }
+
+    #[test]
+    fn test_line_count_matches_str_lines() {
+        for source in ["a\nb", "a\nb\n", "a\n\n", "\n", "a", "a\r\nb\r\n"] {
+            assert_eq!(kinds(source).len(), source.lines().count(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_lone_cr_is_line_break() {
+        let source = "import a from 'a'\r\rimport b from 'b'\r";
+        assert_eq!(
+            kinds(source),
+            vec![LineKind::Import, LineKind::Blank, LineKind::Import]
+        );
+    }
+
+    #[test]
+    fn test_multiline_import_statement() {
+        let source = "import {\n  a,\n  b,\n} from 'x'\nconst y = 1\n";
+        let scan = scan(source);
+        assert_eq!(
+            scan.imports,
+            vec![ImportStatement {
+                start_line: 0,
+                end_line: 3,
+                source: Some("x".to_string()),
+            }]
+        );
+        assert_eq!(scan.lines[4], LineKind::Code);
+    }
+
+    #[test]
+    fn test_brace_inside_string_in_import() {
+        let source = "import {\n  a as '}',\n  b,\n} from 'x'\n";
+        assert_eq!(kinds(source), vec![LineKind::Import; 4]);
+    }
+
+    #[test]
+    fn test_semicolon_terminated_imports_on_one_line() {
+        let scan = scan("import a from 'a'; import b from 'b';\n");
+        assert_eq!(scan.imports.len(), 2);
+        assert_eq!(scan.lines, vec![LineKind::Import]);
+    }
+
+    #[test]
+    fn test_import_with_attributes() {
+        let source = "import data from './data.json' with { type: 'json' }\nconst x = 1\n";
+        let scan = scan(source);
+        assert_eq!(scan.lines, vec![LineKind::Import, LineKind::Code]);
+        assert_eq!(scan.imports[0].source.as_deref(), Some("./data.json"));
+    }
+
+    #[test]
+    fn test_import_sources() {
+        let source = "import a from 'a'\nimport \"./b.css\"\nimport {\n  c,\n} from '@scope/c'\nimport('d')\n";
+        let sources: Vec<_> = scan(source).imports.into_iter().map(|i| i.source).collect();
+        assert_eq!(
+            sources,
+            [
+                Some("a".to_string()),
+                Some("./b.css".to_string()),
+                Some("@scope/c".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_equals_require() {
+        let source = "import fs = require('fs')\nconst x = 1\n";
+        assert_eq!(kinds(source), vec![LineKind::Import, LineKind::Code]);
+    }
+
+    #[test]
+    fn test_jsx_text_is_not_import() {
+        let source = "const el = (\n  <p>\n    import this\n  </p>\n)\n";
+        assert!(kinds(source).iter().all(|k| *k != LineKind::Import));
+    }
+
+    #[test]
+    fn test_template_literal_lines_are_code() {
+        let source = "const s = `\n\nimport x from 'y'\n`\n";
+        assert_eq!(kinds(source), vec![LineKind::Code; 4]);
+    }
+
+    #[test]
+    fn test_template_substitution_nesting() {
+        let source = "const s = `${ {a: `}`}.a }`\nimport x from 'y'\n";
+        assert_eq!(kinds(source), vec![LineKind::Code, LineKind::Import]);
+    }
+
+    #[test]
+    fn test_block_comment_without_stars() {
+        let source = "/*\n  license text\n\n  more text\n*/\n";
+        assert_eq!(kinds(source), vec![LineKind::Comment; 5]);
+    }
+
+    #[test]
+    fn test_regex_literal_with_quote_and_slash() {
+        let source = "const re = /['\"`]\\//g\nimport x from 'y'\n";
+        assert_eq!(kinds(source), vec![LineKind::Code, LineKind::Import]);
+    }
+
+    #[test]
+    fn test_division_is_not_regex() {
+        let source = "const a = b / c / d\nimport x from 'y'\n";
+        assert_eq!(kinds(source), vec![LineKind::Code, LineKind::Import]);
+    }
+
+    #[test]
+    fn test_dynamic_import_in_expression_is_code() {
+        let source = "const m = await import('x')\nconst u = import.meta.url\n";
+        assert_eq!(kinds(source), vec![LineKind::Code; 2]);
+    }
+
+    #[test]
+    fn test_nested_import_is_not_top_level() {
+        let source = "declare module 'x' {\n  import y from 'y'\n}\n";
+        assert!(scan(source).imports.is_empty());
+    }
+
+    #[test]
+    fn test_leading_bom_is_ignored() {
+        let source = "\u{FEFF}import x from 'y'\n";
+        assert_eq!(kinds(source), vec![LineKind::Import]);
+    }
+
+    #[test]
+    fn test_hashbang_is_comment() {
+        let source = "#!/usr/bin/env node\nimport x from 'y'\n";
+        assert_eq!(kinds(source), vec![LineKind::Comment, LineKind::Import]);
+    }
+}
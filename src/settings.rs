@@ -0,0 +1,276 @@
+//! import-squeeze's own settings, for options that have no place in biome.json.
+//!
+//! Read from `import-squeeze.json`, or from the `"importSqueeze"` key of a
+//! `package.json`, found upward from the current directory:
+//!
+//! ```json
+//! {
+//!   "biomeConfig": "config/biome.json",
+//!   "excludes": ["**/*.generated.ts"],
+//!   "extensions": ["vue", "!cjs"],
+//!   "reporter": "json",
+//!   "diff": true,
+//!   "color": "never",
+//!   "changedLinesOnly": false,
+//!   "vcsUseIgnoreFile": true
+//! }
+//! ```
+//!
+//! Command-line flags take precedence over these settings.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Adjustments, Extensions};
+use crate::jsonc;
+
+pub const SETTINGS_FILE: &str = "import-squeeze.json";
+
+/// Key holding the settings in `package.json`.
+pub const PACKAGE_JSON_KEY: &str = "importSqueeze";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Settings {
+    /// biome.json to use, resolved against the settings file's directory.
+    pub biome_config: Option<PathBuf>,
+    /// Exclude patterns added to biome.json's, with the same semantics.
+    pub excludes: Vec<String>,
+    /// Extensions to process besides the default ones, or with a `!` prefix,
+    /// to stop processing, as accepted by `--ext`.
+    pub extensions: Vec<String>,
+    /// Reporter name, as accepted by `--reporter`.
+    pub reporter: Option<String>,
+    pub diff: bool,
+    /// `auto`, `always` or `never`, as accepted by `--color`.
+    pub color: Option<String>,
+    pub changed_lines_only: bool,
+    pub vcs_use_ignore_file: bool,
+}
+
+/// Command-line flags that take precedence over the settings' changes to
+/// biome.json.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// `--vcs-use-ignore-file` (`Some(true)`) or `--no-vcs-use-ignore-file`
+    /// (`Some(false)`).
+    pub use_ignore_file: Option<bool>,
+    /// `--ext` entries, applied after the settings' `extensions`.
+    pub extensions: Vec<String>,
+}
+
+impl Settings {
+    /// The changes to make to biome.json, with `overrides` applied on top.
+    pub fn adjustments(&self, overrides: &Overrides) -> Result<Adjustments> {
+        let mut extensions = Extensions::default();
+        for entry in self.extensions.iter().chain(&overrides.extensions) {
+            extensions.update(entry)?;
+        }
+        Ok(Adjustments {
+            excludes: self.excludes.clone(),
+            use_ignore_file: overrides
+                .use_ignore_file
+                .unwrap_or(self.vcs_use_ignore_file),
+            extensions,
+        })
+    }
+}
+
+/// Find the settings by searching `start_dir`, then its parents. In each
+/// directory, `import-squeeze.json` wins over a `package.json` with an
+/// `"importSqueeze"` key; a `package.json` without one is skipped, and so is
+/// one that can't be parsed, since it may belong to an unrelated project.
+pub fn find_settings(start_dir: &Path) -> Result<Option<(Settings, PathBuf)>> {
+    for dir in start_dir.ancestors() {
+        let file = dir.join(SETTINGS_FILE);
+        if file.is_file() {
+            return load_settings(&file).map(|settings| Some((settings, file)));
+        }
+        let package_json = dir.join("package.json");
+        if let Some(json) = read_json(&package_json)
+            .ok()
+            .flatten()
+            .as_ref()
+            .and_then(|json| json.get(PACKAGE_JSON_KEY))
+        {
+            let settings = settings_from_json(json, dir).with_context(|| {
+                format!("Invalid {} in {}", PACKAGE_JSON_KEY, package_json.display())
+            })?;
+            return Ok(Some((settings, package_json)));
+        }
+    }
+    Ok(None)
+}
+
+/// Read settings from an `import-squeeze.json`, or from the `"importSqueeze"`
+/// key of a `package.json` (the defaults if it has none).
+pub fn load_settings(file: &Path) -> Result<Settings> {
+    let json = read_json(file)?.with_context(|| format!("Failed to read {}", file.display()))?;
+    let dir = file.parent().unwrap_or(Path::new(""));
+    if file.file_name() == Some("package.json".as_ref()) {
+        return match json.get(PACKAGE_JSON_KEY) {
+            Some(json) => settings_from_json(json, dir)
+                .with_context(|| format!("Invalid {} in {}", PACKAGE_JSON_KEY, file.display())),
+            None => Ok(Settings::default()),
+        };
+    }
+    settings_from_json(&json, dir).with_context(|| format!("Invalid {}", file.display()))
+}
+
+/// Parse a JSON(C) file, or `None` if it doesn't exist.
+fn read_json(file: &Path) -> Result<Option<Value>> {
+    if !file.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let json =
+        jsonc::parse(&content).with_context(|| format!("Failed to parse {}", file.display()))?;
+    Ok(Some(json))
+}
+
+/// Build [`Settings`] from their JSON object; relative paths are resolved
+/// against `dir`. Unknown keys and values of the wrong type are errors.
+pub fn settings_from_json(json: &Value, dir: &Path) -> Result<Settings> {
+    let Some(object) = json.as_object() else {
+        bail!("settings must be an object");
+    };
+    let mut settings = Settings::default();
+    for (key, value) in object {
+        match key.as_str() {
+            "$schema" => {}
+            "biomeConfig" => settings.biome_config = Some(dir.join(string(key, value)?)),
+            "excludes" => settings.excludes = strings(key, value)?,
+            "extensions" => {
+                settings.extensions = strings(key, value)?;
+                // Catch bad entries here, where the file is known.
+                let mut extensions = Extensions::default();
+                for entry in &settings.extensions {
+                    extensions.update(entry)?;
+                }
+            }
+            "reporter" => settings.reporter = Some(string(key, value)?.to_string()),
+            "diff" => settings.diff = boolean(key, value)?,
+            "color" => settings.color = Some(string(key, value)?.to_string()),
+            "changedLinesOnly" => settings.changed_lines_only = boolean(key, value)?,
+            "vcsUseIgnoreFile" => settings.vcs_use_ignore_file = boolean(key, value)?,
+            _ => bail!("unknown option \"{}\"", key),
+        }
+    }
+    Ok(settings)
+}
+
+fn string<'a>(key: &str, value: &'a Value) -> Result<&'a str> {
+    value
+        .as_str()
+        .with_context(|| format!("\"{}\" must be a string", key))
+}
+
+fn strings(key: &str, value: &Value) -> Result<Vec<String>> {
+    value
+        .as_array()
+        .with_context(|| format!("\"{}\" must be an array of strings", key))?
+        .iter()
+        .map(|item| string(key, item).map(String::from))
+        .collect()
+}
+
+fn boolean(key: &str, value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .with_context(|| format!("\"{}\" must be true or false", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_settings_from_json() {
+        let json = serde_json::json!({
+            "biomeConfig": "config/biome.json",
+            "excludes": ["**/*.gen.ts"],
+            "extensions": ["vue"],
+            "reporter": "json",
+            "diff": true,
+            "vcsUseIgnoreFile": true
+        });
+        let settings = settings_from_json(&json, Path::new("/repo")).unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                biome_config: Some(PathBuf::from("/repo/config/biome.json")),
+                excludes: vec!["**/*.gen.ts".to_string()],
+                extensions: vec!["vue".to_string()],
+                reporter: Some("json".to_string()),
+                diff: true,
+                vcs_use_ignore_file: true,
+                ..Default::default()
+            }
+        );
+
+        let err = settings_from_json(&serde_json::json!({"dif": true}), Path::new("")).unwrap_err();
+        assert_eq!(err.to_string(), "unknown option \"dif\"");
+        let err =
+            settings_from_json(&serde_json::json!({"diff": "yes"}), Path::new("")).unwrap_err();
+        assert_eq!(err.to_string(), "\"diff\" must be true or false");
+    }
+
+    #[test]
+    fn test_adjustments_with_overrides() {
+        let settings = Settings {
+            excludes: vec!["dist".to_string()],
+            extensions: vec!["!cjs".to_string()],
+            vcs_use_ignore_file: true,
+            ..Default::default()
+        };
+        let adjustments = settings.adjustments(&Overrides::default()).unwrap();
+        assert_eq!(adjustments.excludes, ["dist"]);
+        assert!(adjustments.use_ignore_file);
+        assert!(!adjustments.extensions.matches(Path::new("a.cjs")));
+
+        let overrides = Overrides {
+            use_ignore_file: Some(false),
+            extensions: vec!["cjs".to_string()],
+        };
+        let adjustments = settings.adjustments(&overrides).unwrap();
+        assert!(!adjustments.use_ignore_file);
+        assert!(adjustments.extensions.matches(Path::new("a.cjs")));
+    }
+
+    #[test]
+    fn test_find_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let app = root.join("packages/app");
+        fs::create_dir_all(&app).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"importSqueeze": {"diff": true}}"#,
+        )
+        .unwrap();
+        // A package.json without the key doesn't stop the search.
+        fs::write(app.join("package.json"), r#"{"name": "app"}"#).unwrap();
+
+        let (settings, file) = find_settings(&app).unwrap().unwrap();
+        assert!(settings.diff);
+        assert_eq!(file, root.join("package.json"));
+        assert_eq!(load_settings(&file).unwrap(), settings);
+
+        // Nor does one that isn't valid JSON.
+        fs::write(app.join("package.json"), "{ not json").unwrap();
+        let (_, file) = find_settings(&app).unwrap().unwrap();
+        assert_eq!(file, root.join("package.json"));
+
+        fs::write(
+            app.join(SETTINGS_FILE),
+            "{\n  // local\n  \"reporter\": \"sarif\",\n}",
+        )
+        .unwrap();
+        let (settings, file) = find_settings(&app).unwrap().unwrap();
+        assert_eq!(settings.reporter.as_deref(), Some("sarif"));
+        assert!(!settings.diff);
+        assert_eq!(file, app.join(SETTINGS_FILE));
+    }
+}
@@ -0,0 +1,148 @@
+//! Ignore files honored by Biome's VCS integration (`vcs.useIgnoreFile`):
+//! `.gitignore` and `.ignore` in every directory of the repository, and
+//! `.git/info/exclude`.
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+
+use crate::config::normalize;
+
+/// Ignore files in a directory, highest precedence first.
+const IGNORE_FILES: &[&str] = &[".ignore", ".gitignore"];
+
+/// The ignore files that apply at some position of a walk: those of the
+/// directories from the repository root down to the current one.
+///
+/// Deeper files win over shallower ones, `.ignore` wins over `.gitignore` in
+/// the same directory, and `.git/info/exclude` comes last. Outside a git
+/// repository, the walk's starting directory stands in for the root.
+pub struct IgnoreFiles {
+    cwd: PathBuf,
+    exclude: Option<Gitignore>,
+    /// Directories entered so far, outermost first, with their ignore files.
+    dirs: Vec<(PathBuf, Vec<Gitignore>)>,
+}
+
+impl IgnoreFiles {
+    /// Ignore files for a walk starting at `dir`.
+    pub fn new(dir: &Path) -> Self {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let dir = normalize(&cwd.join(dir));
+        let root = dir
+            .ancestors()
+            .find(|d| d.join(".git").exists())
+            .unwrap_or(&dir)
+            .to_path_buf();
+
+        let mut ignore_files = IgnoreFiles {
+            cwd,
+            exclude: load(&root, &root.join(".git/info/exclude")),
+            dirs: Vec::new(),
+        };
+        let mut below_root: Vec<&Path> = dir.ancestors().take_while(|d| *d != root).collect();
+        below_root.push(&root);
+        for d in below_root.into_iter().rev() {
+            ignore_files.enter(d);
+        }
+        ignore_files
+    }
+
+    /// Move the walk position to `dir`, which must be the directory last
+    /// entered, one of its ancestors, or a child of one of those.
+    pub fn enter(&mut self, dir: &Path) {
+        let dir = normalize(&self.cwd.join(dir));
+        while self.dirs.last().is_some_and(|(d, _)| !dir.starts_with(d)) {
+            self.dirs.pop();
+        }
+        if self.dirs.last().is_some_and(|(d, _)| *d == dir) {
+            return;
+        }
+        let files = IGNORE_FILES
+            .iter()
+            .filter_map(|name| load(&dir, &dir.join(name)))
+            .collect();
+        self.dirs.push((dir, files));
+    }
+
+    /// Whether `path` (in a directory entered last) or a directory above it is
+    /// ignored.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = normalize(&self.cwd.join(path));
+        let matchers = self
+            .dirs
+            .iter()
+            .rev()
+            .flat_map(|(_, files)| files)
+            .chain(&self.exclude);
+        for matcher in matchers {
+            if !path.starts_with(matcher.path()) {
+                continue;
+            }
+            match matcher.matched_path_or_any_parents(&path, is_dir) {
+                Match::None => {}
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+            }
+        }
+        false
+    }
+}
+
+/// Parse the ignore file at `file`, with patterns relative to `root`. Missing
+/// or unreadable files, and invalid lines, are skipped like git does.
+fn load(root: &Path, file: &Path) -> Option<Gitignore> {
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(root);
+    builder.add(file);
+    builder
+        .build()
+        .ok()
+        .filter(|gitignore| !gitignore.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_nested_ignore_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::create_dir_all(root.join("pkg/src")).unwrap();
+        fs::write(root.join(".gitignore"), "dist/\n*.gen.ts\n").unwrap();
+        fs::write(root.join(".git/info/exclude"), "scratch.ts\n").unwrap();
+        fs::write(root.join("pkg/.gitignore"), "!keep.gen.ts\nlocal.ts\n").unwrap();
+        fs::write(root.join("pkg/.ignore"), "!local.ts\nvendor\n").unwrap();
+
+        let ignore_files = IgnoreFiles::new(&root.join("pkg/src"));
+        for (path, expected) in [
+            ("pkg/src/a.ts", false),
+            ("pkg/src/a.gen.ts", true),
+            ("pkg/src/keep.gen.ts", false),
+            ("pkg/src/local.ts", false),
+            ("pkg/src/vendor/a.ts", true),
+            ("pkg/src/dist/a.ts", true),
+            ("pkg/src/scratch.ts", true),
+        ] {
+            assert_eq!(
+                ignore_files.is_ignored(&root.join(path), false),
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        // Leaving `pkg` drops its ignore files.
+        let mut ignore_files = IgnoreFiles::new(&root);
+        ignore_files.enter(&root.join("pkg"));
+        assert!(!ignore_files.is_ignored(&root.join("pkg/keep.gen.ts"), false));
+        ignore_files.enter(&root.join("other"));
+        assert!(ignore_files.is_ignored(&root.join("other/keep.gen.ts"), false));
+        assert!(ignore_files.is_ignored(&root.join("dist"), true));
+    }
+}
@@ -0,0 +1,524 @@
+//! `--watch`: keep running and re-squeeze files when they change on disk.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::config::{self, Matcher};
+use crate::report::FileReport;
+use crate::settings::{self, Settings};
+use crate::{process_bytes_with_options, FileResult, SqueezeOptions};
+
+/// How long the file system has to be quiet before a batch of changes is processed.
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Which files to watch.
+#[derive(Debug, Clone)]
+pub enum Scope {
+    /// Exactly these files, as passed on the command line.
+    Files(Vec<PathBuf>),
+    /// Files selected by this biome.json. It is reloaded when it, a config it
+    /// extends, a nested config or the settings file changes.
+    Config(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub check: bool,
+    /// `Some(color)` to render a diff for each changed file.
+    pub diff_color: Option<bool>,
+    pub debounce: Duration,
+    /// In `Scope::Config` mode, these are replaced by the config's on every
+    /// reload.
+    pub squeeze: SqueezeOptions,
+    /// import-squeeze.json (or package.json) the settings come from, re-read on
+    /// every reload in `Scope::Config` mode.
+    pub settings_file: Option<PathBuf>,
+    /// Command-line flags applied over the settings.
+    pub overrides: settings::Overrides,
+}
+
+/// Watch `scope` until the watcher shuts down, calling `report` with the outcome
+/// (and diff, if requested) of every batch of re-processed files.
+pub fn run(
+    scope: Scope,
+    options: Options,
+    mut report: impl FnMut(Vec<FileReport>, Vec<Option<String>>),
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .context("Failed to start file watcher")?;
+
+    let mut state = State::new(
+        scope,
+        options.squeeze,
+        options.settings_file,
+        options.overrides,
+    )?;
+    state.watch_all(&mut watcher)?;
+    let mut writes = WriteLog::default();
+
+    while let Some(batch) = next_batch(&rx, options.debounce) {
+        let mut changed: BTreeSet<PathBuf> = BTreeSet::new();
+        for event in batch {
+            let Ok(event) = event else { continue };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+            changed.extend(event.paths);
+        }
+
+        if state.is_config_change(&changed) {
+            match state.reload() {
+                Ok(warnings) => {
+                    eprintln!("Reloaded {}", state.config_label());
+                    for warning in warnings {
+                        eprintln!("Warning: {}: {}", state.config_label(), warning);
+                    }
+                    state.unwatch_all(&mut watcher);
+                    state.watch_all(&mut watcher)?;
+                }
+                Err(e) => eprintln!("Error reloading {}: {:#}", state.config_label(), e),
+            }
+        }
+
+        let mut reports = Vec::new();
+        let mut diffs = Vec::new();
+        for path in changed {
+            if path.is_dir() {
+                // New directories need their own watch in non-recursive mode.
+                state.watch_new_dir(&mut watcher, &path);
+                continue;
+            }
+            if !state.should_process(&path) {
+                continue;
+            }
+            let Ok(bytes) = fs::read(&path) else { continue };
+            if writes.is_own_write(&path, &bytes) {
+                continue;
+            }
+
+            let processed = process_bytes_with_options(
+                &bytes,
+                &path.display().to_string(),
+                options.diff_color,
+                &state.squeeze_options(&path),
+            );
+            let mut result = Ok(processed.result);
+            if let (Some(output), false) = (&processed.output, options.check) {
+                match fs::write(&path, output) {
+                    Ok(()) => writes.record(&path, output),
+                    Err(e) => result = Err(e.to_string()),
+                }
+            }
+            if matches!(result, Ok(FileResult::Unchanged)) {
+                continue;
+            }
+            reports.push(FileReport { path, result });
+            diffs.push(processed.diff);
+        }
+
+        if !reports.is_empty() {
+            report(reports, diffs);
+        }
+    }
+
+    Ok(())
+}
+
+/// Block for the next event, then collect further events until none arrive for
+/// `debounce`. Returns `None` once the sender is gone.
+pub fn next_batch<T>(rx: &Receiver<T>, debounce: Duration) -> Option<Vec<T>> {
+    let mut batch = vec![rx.recv().ok()?];
+    loop {
+        match rx.recv_timeout(debounce) {
+            Ok(event) => batch.push(event),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                return Some(batch)
+            }
+        }
+    }
+}
+
+/// Content hashes of files we wrote ourselves, so the resulting change events
+/// don't trigger another pass.
+#[derive(Debug, Default)]
+pub struct WriteLog {
+    written: HashMap<PathBuf, u64>,
+}
+
+impl WriteLog {
+    pub fn record(&mut self, path: &Path, bytes: &[u8]) {
+        self.written.insert(path.to_path_buf(), hash(bytes));
+    }
+
+    /// Whether `bytes` is exactly what we last wrote to `path`. Each write is
+    /// matched at most once; any other content clears the entry.
+    pub fn is_own_write(&mut self, path: &Path, bytes: &[u8]) -> bool {
+        self.written.remove(path) == Some(hash(bytes))
+    }
+}
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct State {
+    scope: Scope,
+    /// Loaded config, in `Scope::Config` mode.
+    config: Option<Matcher>,
+    squeeze: SqueezeOptions,
+    settings_file: Option<PathBuf>,
+    overrides: settings::Overrides,
+    /// Files that trigger a reload, in `Scope::Config` mode: the config,
+    /// everything it extends and the settings file. Nested configs are
+    /// recognized by name.
+    config_files: Vec<PathBuf>,
+    files: HashSet<PathBuf>,
+    watched: Vec<PathBuf>,
+}
+
+impl State {
+    fn new(
+        scope: Scope,
+        squeeze: SqueezeOptions,
+        settings_file: Option<PathBuf>,
+        overrides: settings::Overrides,
+    ) -> Result<Self> {
+        let scope = match scope {
+            Scope::Files(files) => {
+                Scope::Files(files.iter().map(|f| canonical(f)).collect::<Result<_>>()?)
+            }
+            Scope::Config(path) => Scope::Config(canonical(&path)?),
+        };
+        let mut state = State {
+            scope,
+            config: None,
+            squeeze,
+            settings_file: settings_file.map(|f| canonical(&f)).transpose()?,
+            overrides,
+            config_files: Vec::new(),
+            files: HashSet::new(),
+            watched: Vec::new(),
+        };
+        // Warnings were shown when the config was first loaded.
+        state.reload()?;
+        Ok(state)
+    }
+
+    /// Re-read the scope. Returns the config's warnings, if there is one.
+    fn reload(&mut self) -> Result<Vec<String>> {
+        match &self.scope {
+            Scope::Files(files) => {
+                self.files = files.iter().cloned().collect();
+                Ok(Vec::new())
+            }
+            Scope::Config(path) => {
+                let settings = match &self.settings_file {
+                    Some(file) => settings::load_settings(file)?,
+                    None => Settings::default(),
+                };
+                let (mut config, base_dir) = config::load_biome_config(path)?;
+                settings.adjustments(&self.overrides)?.apply(&mut config);
+                self.config = Some(Matcher::new(&config, &base_dir)?);
+                self.squeeze = config.squeeze_options();
+                self.config_files = config.sources;
+                self.config_files.extend(self.settings_file.clone());
+                Ok(config.warnings)
+            }
+        }
+    }
+
+    fn config_label(&self) -> String {
+        match &self.scope {
+            Scope::Config(path) => path.display().to_string(),
+            Scope::Files(_) => String::new(),
+        }
+    }
+
+    fn is_config_change(&self, changed: &BTreeSet<PathBuf>) -> bool {
+        let Some(matcher) = &self.config else {
+            return false;
+        };
+        changed.iter().any(|path| {
+            let is_nested_config = path.starts_with(matcher.base_dir())
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| config::CONFIG_FILE_NAMES.contains(&name));
+            is_nested_config || self.config_files.contains(path)
+        })
+    }
+
+    fn should_process(&self, path: &Path) -> bool {
+        match &self.config {
+            Some(matcher) => config::is_included_with_nested(matcher, path).unwrap_or(false),
+            None => self.files.contains(path),
+        }
+    }
+
+    /// The options of the nested config that applies to `path`, if any, else
+    /// those of the root config. A broken nested config already keeps
+    /// [`State::should_process`] from selecting the file.
+    fn squeeze_options(&self, path: &Path) -> SqueezeOptions {
+        self.config
+            .as_ref()
+            .and_then(|matcher| config::nested_squeeze_options(matcher, path).ok().flatten())
+            .unwrap_or_else(|| self.squeeze.clone())
+    }
+
+    /// Directories to watch non-recursively: every directory under the config's
+    /// base directory that is neither excluded nor ignored, or the parents of
+    /// explicitly passed files.
+    fn dirs(&self) -> Vec<PathBuf> {
+        match &self.config {
+            Some(matcher) => {
+                let ignore_files = matcher.ignore_files().map(RefCell::new);
+                WalkDir::new(matcher.base_dir())
+                    .follow_links(false)
+                    .into_iter()
+                    .filter_entry(|e| {
+                        if !e.file_type().is_dir() || matcher.is_excluded_dir(e.path()) {
+                            return false;
+                        }
+                        if let Some(ignore_files) = &ignore_files {
+                            let mut ignore_files = ignore_files.borrow_mut();
+                            if ignore_files.is_ignored(e.path(), true) {
+                                return false;
+                            }
+                            ignore_files.enter(e.path());
+                        }
+                        true
+                    })
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.into_path())
+                    .collect()
+            }
+            None => {
+                let dirs: BTreeSet<PathBuf> = self
+                    .files
+                    .iter()
+                    .filter_map(|f| f.parent().map(Path::to_path_buf))
+                    .collect();
+                dirs.into_iter().collect()
+            }
+        }
+    }
+
+    /// [`State::dirs`] plus the directories of the config files, which may live
+    /// outside the walked tree (`--config`, extended configs, a settings file
+    /// above the project).
+    fn watch_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = self.dirs();
+        for file in &self.config_files {
+            if let Some(parent) = file.parent() {
+                if !dirs.iter().any(|d| d == parent) {
+                    dirs.push(parent.to_path_buf());
+                }
+            }
+        }
+        dirs
+    }
+
+    fn watch_all(&mut self, watcher: &mut RecommendedWatcher) -> Result<()> {
+        for dir in self.watch_dirs() {
+            watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+            self.watched.push(dir);
+        }
+        Ok(())
+    }
+
+    fn unwatch_all(&mut self, watcher: &mut RecommendedWatcher) {
+        for dir in self.watched.drain(..) {
+            let _ = watcher.unwatch(&dir);
+        }
+    }
+
+    fn watch_new_dir(&mut self, watcher: &mut RecommendedWatcher, dir: &Path) {
+        let Some(matcher) = &self.config else {
+            return;
+        };
+        if !dir.starts_with(matcher.base_dir()) || self.watched.iter().any(|d| d == dir) {
+            return;
+        }
+        let excluded = dir
+            .ancestors()
+            .take_while(|a| *a != matcher.base_dir())
+            .any(|a| matcher.is_excluded_dir(a));
+        let ignored = matcher.ignore_files().is_some_and(|mut ignore_files| {
+            let parents: Vec<&Path> = dir
+                .ancestors()
+                .skip(1)
+                .take_while(|a| a.starts_with(matcher.base_dir()))
+                .collect();
+            for parent in parents.into_iter().rev() {
+                ignore_files.enter(parent);
+            }
+            ignore_files.is_ignored(dir, true)
+        });
+        if !excluded && !ignored && watcher.watch(dir, RecursiveMode::NonRecursive).is_ok() {
+            self.watched.push(dir.to_path_buf());
+        }
+    }
+}
+
+fn canonical(path: &Path) -> Result<PathBuf> {
+    fs::canonicalize(path).with_context(|| format!("Failed to resolve {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_next_batch_debounces_bursts() {
+        let (tx, rx) = mpsc::channel();
+        let sender = thread::spawn(move || {
+            for i in 0..5 {
+                tx.send(i).unwrap();
+                thread::sleep(Duration::from_millis(5));
+            }
+            thread::sleep(Duration::from_millis(300));
+            tx.send(99).unwrap();
+        });
+
+        let start = Instant::now();
+        assert_eq!(
+            next_batch(&rx, Duration::from_millis(100)),
+            Some(vec![0, 1, 2, 3, 4])
+        );
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(next_batch(&rx, Duration::from_millis(100)), Some(vec![99]));
+        sender.join().unwrap();
+        assert_eq!(next_batch(&rx, Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn test_write_log_skips_own_writes_once() {
+        let mut log = WriteLog::default();
+        let path = Path::new("/tmp/a.ts");
+        log.record(path, b"import a from 'a'\n");
+
+        assert!(log.is_own_write(path, b"import a from 'a'\n"));
+        // A second event for the same content is a real (if unusual) change.
+        assert!(!log.is_own_write(path, b"import a from 'a'\n"));
+
+        log.record(path, b"one");
+        assert!(!log.is_own_write(path, b"two"));
+        assert!(!log.is_own_write(path, b"one"));
+    }
+
+    #[test]
+    fn test_state_selects_files_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::write(
+            root.join("biome.json"),
+            r#"{"files": {"include": ["src/**"]}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src/node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("lib")).unwrap();
+
+        let scope = Scope::Config(root.join("biome.json"));
+        let state = State::new(scope, SqueezeOptions::default(), None, Default::default()).unwrap();
+        assert!(state.should_process(&root.join("src/new.ts")));
+        assert!(!state.should_process(&root.join("lib/a.ts")));
+        assert!(!state.should_process(&root.join("src/node_modules/pkg/a.ts")));
+
+        let dirs = state.dirs();
+        assert!(dirs.contains(&root.join("src")));
+        assert!(!dirs.iter().any(|d| d.ends_with("node_modules")));
+    }
+
+    #[test]
+    fn test_dirs_skip_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::write(
+            root.join("biome.json"),
+            r#"{"vcs": {"enabled": true, "useIgnoreFile": true}}"#,
+        )
+        .unwrap();
+        fs::write(root.join(".gitignore"), "generated/\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("generated/deep")).unwrap();
+
+        let scope = Scope::Config(root.join("biome.json"));
+        let state = State::new(scope, SqueezeOptions::default(), None, Default::default()).unwrap();
+        let dirs = state.dirs();
+        assert!(dirs.contains(&root.join("src")));
+        assert!(!dirs.iter().any(|d| d.starts_with(root.join("generated"))));
+    }
+
+    #[test]
+    fn test_extended_config_change_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join("app/src")).unwrap();
+        fs::create_dir_all(root.join("shared")).unwrap();
+        fs::write(
+            root.join("shared/base.json"),
+            r#"{"files": {"includes": ["src/**"]}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("app/biome.json"),
+            r#"{"extends": ["../shared/base.json"]}"#,
+        )
+        .unwrap();
+
+        let scope = Scope::Config(root.join("app/biome.json"));
+        let state = State::new(scope, SqueezeOptions::default(), None, Default::default()).unwrap();
+        let change = |path: &str| state.is_config_change(&BTreeSet::from([root.join(path)]));
+        assert!(change("app/biome.json"));
+        assert!(change("shared/base.json"));
+        assert!(change("app/src/pkg/biome.json"));
+        assert!(!change("app/src/a.ts"));
+        assert!(state.watch_dirs().contains(&root.join("shared")));
+    }
+
+    #[test]
+    fn test_settings_change_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("biome.json"), "{}").unwrap();
+        fs::write(root.join("package.json"), r#"{"importSqueeze": {}}"#).unwrap();
+
+        let mut state = State::new(
+            Scope::Config(root.join("biome.json")),
+            SqueezeOptions::default(),
+            Some(root.join("package.json")),
+            Default::default(),
+        )
+        .unwrap();
+        assert!(state.is_config_change(&BTreeSet::from([root.join("package.json")])));
+
+        assert!(state.should_process(&root.join("src/a.gen.ts")));
+        fs::write(
+            root.join("package.json"),
+            r#"{"importSqueeze": {"excludes": ["**/*.gen.ts"]}}"#,
+        )
+        .unwrap();
+        state.reload().unwrap();
+        assert!(!state.should_process(&root.join("src/a.gen.ts")));
+        assert!(state.should_process(&root.join("src/a.ts")));
+    }
+}
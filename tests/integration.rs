@@ -1,4 +1,8 @@
 use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use import_squeeze::{process_file, squeeze_imports, FileResult};
 
@@ -17,7 +21,12 @@ fn test_process_file_write_mode() {
     .unwrap();
 
     let result = process_file(&file_path, false).unwrap();
-    assert_eq!(result, FileResult::Changed);
+    assert_eq!(
+        result,
+        FileResult::Changed {
+            removed_lines: vec![2]
+        }
+    );
 
     let content = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content, "import { a } from 'a'\nimport { b } from 'b'\n\nconst x = 1\n");
@@ -31,7 +40,12 @@ fn test_process_file_check_mode() {
     fs::write(&file_path, original).unwrap();
 
     let result = process_file(&file_path, true).unwrap();
-    assert_eq!(result, FileResult::Changed);
+    assert_eq!(
+        result,
+        FileResult::Changed {
+            removed_lines: vec![2]
+        }
+    );
 
     // File should NOT be modified in check mode
     let content = fs::read_to_string(&file_path).unwrap();
@@ -52,6 +66,71 @@ fn test_process_file_unchanged() {
     assert_eq!(result, FileResult::Unchanged);
 }
 
+#[test]
+fn test_process_file_crlf_unchanged() {
+    let dir = create_temp_dir();
+    let file_path = dir.path().join("test.ts");
+    let original = "import { a } from 'a'\r\nimport { b } from 'b'\r\n\r\nconst x = 1\r\n";
+    fs::write(&file_path, original).unwrap();
+
+    let result = process_file(&file_path, false).unwrap();
+    assert_eq!(result, FileResult::Unchanged);
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), original);
+}
+
+#[test]
+fn test_process_file_preserves_utf8_bom() {
+    let dir = create_temp_dir();
+    let file_path = dir.path().join("test.ts");
+    fs::write(
+        &file_path,
+        "\u{FEFF}import { a } from 'a'\n\nimport { b } from 'b'\n",
+    )
+    .unwrap();
+
+    let result = process_file(&file_path, false).unwrap();
+    assert!(matches!(result, FileResult::Changed { .. }));
+    assert_eq!(
+        fs::read_to_string(&file_path).unwrap(),
+        "\u{FEFF}import { a } from 'a'\nimport { b } from 'b'\n"
+    );
+}
+
+#[test]
+fn test_process_file_utf16_le() {
+    let dir = create_temp_dir();
+    let file_path = dir.path().join("test.ts");
+    let encode = |text: &str| -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        bytes
+    };
+    fs::write(
+        &file_path,
+        encode("import { a } from 'a'\n\nimport { b } from 'b'\n"),
+    )
+    .unwrap();
+
+    let result = process_file(&file_path, false).unwrap();
+    assert!(matches!(result, FileResult::Changed { .. }));
+    assert_eq!(
+        fs::read(&file_path).unwrap(),
+        encode("import { a } from 'a'\nimport { b } from 'b'\n")
+    );
+}
+
+#[test]
+fn test_process_file_skips_undecodable() {
+    let dir = create_temp_dir();
+    let file_path = dir.path().join("test.js");
+    let original = b"// caf\xE9\nimport { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::write(&file_path, original).unwrap();
+
+    let result = process_file(&file_path, false).unwrap();
+    assert!(matches!(result, FileResult::Skipped(_)));
+    assert_eq!(fs::read(&file_path).unwrap(), original);
+}
+
 #[test]
 fn test_squeeze_complex_real_world() {
     let input = r#"// @ts-nocheck
@@ -131,3 +210,403 @@ fn test_biome_config_file_discovery() {
     assert_eq!(files.len(), 1);
     assert!(files[0].ends_with("test.ts"));
 }
+
+fn command(dir: &std::path::Path, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_import-squeeze"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env_remove("GITHUB_ACTIONS")
+        .env_remove("GITLAB_CI");
+    command
+}
+
+/// Run the CLI without input. Its stdin is left open and never written to, so
+/// an invocation that reads stdin hangs and fails instead of seeing an empty input.
+fn run(dir: &std::path::Path, args: &[&str]) -> Output {
+    let mut child = command(dir, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let _stdin = child.stdin.take();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    while child.try_wait().unwrap().is_none() {
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("import-squeeze {:?} is waiting for stdin", args);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn run_with_stdin(dir: &std::path::Path, args: &[&str], input: &str) -> Output {
+    let mut child = command(dir, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_mode() {
+    let dir = create_temp_dir();
+    let input = "import { a } from 'a'\r\n\r\nimport { b } from 'b'\r\n";
+
+    let output = run_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filepath", "src/App.tsx"],
+        input,
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "import { a } from 'a'\r\nimport { b } from 'b'\r\n"
+    );
+
+    let output = run_with_stdin(dir.path(), &["--stdin", "--check"], input);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_stdin_mode_respects_config_and_extension() {
+    let dir = create_temp_dir();
+    fs::write(
+        dir.path().join("biome.json"),
+        r#"{"files": {"include": ["src/**"]}}"#,
+    )
+    .unwrap();
+    let input = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+
+    for path in ["lib/index.ts", "src/styles.css"] {
+        let output = run_with_stdin(dir.path(), &["--stdin", "--stdin-filepath", path], input);
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), input, "{}", path);
+    }
+
+    let output = run_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filepath", "src/index.ts"],
+        input,
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "import { a } from 'a'\nimport { b } from 'b'\n"
+    );
+}
+
+#[test]
+fn test_file_mode_does_not_read_stdin() {
+    let dir = create_temp_dir();
+    fs::write(
+        dir.path().join("index.ts"),
+        "import { a } from 'a'\n\nimport { b } from 'b'\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["--check", "index.ts"]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "index.ts\n");
+}
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+#[test]
+fn test_changed_since_intersects_with_config() {
+    let dir = create_temp_dir();
+    let root = dir.path();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::write(
+        root.join("biome.json"),
+        r#"{"files": {"include": ["src/**"]}}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("lib")).unwrap();
+    fs::write(root.join("src/old.ts"), source).unwrap();
+    git(root, &["init", "-q"]);
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "base"]);
+    git(root, &["branch", "base"]);
+
+    fs::write(root.join("src/new.ts"), source).unwrap();
+    fs::write(root.join("lib/new.ts"), source).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "change"]);
+
+    let output = run(root, &["--changed-since", "base", "--check"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.trim(),
+        std::path::Path::new("src").join("new.ts").to_str().unwrap()
+    );
+}
+
+#[test]
+fn test_blank_line_groups_converge() {
+    let dir = create_temp_dir();
+    fs::write(
+        dir.path().join("biome.json"),
+        r#"{
+  "assist": { "actions": { "source": { "organizeImports": {
+    "level": "on",
+    "options": { "groups": [":NODE:", ":BLANK_LINE:", ":PACKAGE:", ":PATH:"] }
+  } } } }
+}"#,
+    )
+    .unwrap();
+    let file_path = dir.path().join("index.ts");
+    fs::write(
+        &file_path,
+        "import fs from 'node:fs'\n\n\nimport a from 'a'\n\nimport b from './b'\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["index.ts"]);
+    assert!(output.status.success());
+    let expected = "import fs from 'node:fs'\n\nimport a from 'a'\nimport b from './b'\n";
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), expected);
+
+    let output = run(dir.path(), &["--check", "index.ts"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_explicit_files_skip_disabled_organize_imports() {
+    let dir = create_temp_dir();
+    fs::write(
+        dir.path().join("biome.json"),
+        r#"{"overrides": [{"includes": ["legacy/**"], "assist": {"enabled": false}}]}"#,
+    )
+    .unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::create_dir_all(dir.path().join("legacy")).unwrap();
+    fs::write(dir.path().join("legacy/old.ts"), source).unwrap();
+    fs::write(dir.path().join("new.ts"), source).unwrap();
+
+    let output = run(dir.path(), &["--check", "legacy/old.ts", "new.ts"]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "new.ts");
+}
+
+#[test]
+fn test_nested_config_groups_apply_to_single_files() {
+    let dir = create_temp_dir();
+    let root = dir.path();
+    fs::write(root.join("biome.json"), r#"{"files": {"includes": ["**"]}}"#).unwrap();
+    fs::create_dir_all(root.join("packages/app/src")).unwrap();
+    fs::write(
+        root.join("packages/app/biome.json"),
+        r#"{
+  "root": false,
+  "assist": { "actions": { "source": { "organizeImports": {
+    "level": "on",
+    "options": { "groups": [":NODE:", ":BLANK_LINE:", ":PACKAGE:"] }
+  } } } }
+}"#,
+    )
+    .unwrap();
+    let input = "import fs from 'node:fs'\n\nimport a from 'a'\n\nimport b from 'b'\n";
+    let nested = "import fs from 'node:fs'\n\nimport a from 'a'\nimport b from 'b'\n";
+
+    let stdin = |path: &str| {
+        let output = run_with_stdin(root, &["--stdin", "--stdin-filepath", path], input);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(stdin("packages/app/src/index.ts"), nested);
+    assert_eq!(
+        stdin("src/index.ts"),
+        "import fs from 'node:fs'\nimport a from 'a'\nimport b from 'b'\n"
+    );
+
+    let file_path = root.join("packages/app/src/index.ts");
+    fs::write(&file_path, input).unwrap();
+    let output = run(root, &["packages/app/src/index.ts"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), nested);
+}
+
+#[test]
+fn test_vcs_use_ignore_file_flag() {
+    let dir = create_temp_dir();
+    fs::write(dir.path().join("biome.json"), "{}").unwrap();
+    fs::write(dir.path().join(".gitignore"), "dist/\n").unwrap();
+    fs::create_dir_all(dir.path().join("dist")).unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::write(dir.path().join("dist/out.ts"), source).unwrap();
+    fs::write(dir.path().join("index.ts"), source).unwrap();
+
+    let output = run(dir.path(), &["--check", "--reporter", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["files"].as_array().unwrap().len(), 2);
+
+    let output = run(dir.path(), &["--check", "--reporter", "json", "--vcs-use-ignore-file"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(files[0]["path"].as_str().unwrap().ends_with("index.ts"));
+}
+
+#[test]
+fn test_settings_file_with_cli_precedence() {
+    let dir = create_temp_dir();
+    fs::write(dir.path().join("biome.json"), "{}").unwrap();
+    fs::write(
+        dir.path().join("import-squeeze.json"),
+        r#"{"reporter": "json", "excludes": ["**/*.gen.ts"]}"#,
+    )
+    .unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::write(dir.path().join("index.ts"), source).unwrap();
+    fs::write(dir.path().join("types.gen.ts"), source).unwrap();
+
+    let output = run(dir.path(), &["--check"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(files[0]["path"].as_str().unwrap().ends_with("index.ts"));
+
+    let output = run(dir.path(), &["--check", "--reporter", "text"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(serde_json::from_str::<serde_json::Value>(&stdout).is_err());
+    assert!(stdout.contains("index.ts"));
+    assert!(!stdout.contains("types.gen.ts"));
+
+    fs::write(
+        dir.path().join("import-squeeze.json"),
+        r#"{"reporter": "xml"}"#,
+    )
+    .unwrap();
+    let output = run(dir.path(), &["--check"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid reporter \"xml\""));
+}
+
+#[test]
+fn test_extensions_from_settings_and_flag() {
+    let dir = create_temp_dir();
+    fs::write(dir.path().join("biome.json"), "{}").unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    for name in ["a.ts", "b.mts", "c.cjs", "d.mjs", "e.vue"] {
+        fs::write(dir.path().join(name), source).unwrap();
+    }
+    let checked = |args: &[&str]| -> Vec<String> {
+        let args = [&["--check", "--reporter", "json"], args].concat();
+        let output = run(dir.path(), &args);
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let mut paths: Vec<String> = report["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                let path = std::path::Path::new(f["path"].as_str().unwrap());
+                path.file_name().unwrap().to_string_lossy().into_owned()
+            })
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    assert_eq!(checked(&[]), ["a.ts", "b.mts", "c.cjs", "d.mjs"]);
+    assert_eq!(
+        checked(&["--ext", "vue,!cjs"]),
+        ["a.ts", "b.mts", "d.mjs", "e.vue"]
+    );
+
+    fs::write(
+        dir.path().join("import-squeeze.json"),
+        r#"{"extensions": ["!mjs", "!cjs"]}"#,
+    )
+    .unwrap();
+    assert_eq!(checked(&[]), ["a.ts", "b.mts"]);
+    // The flag is applied after the settings.
+    assert_eq!(checked(&["--ext", ".mjs"]), ["a.ts", "b.mts", "d.mjs"]);
+}
+
+#[test]
+fn test_settings_booleans_overridden_by_no_flags() {
+    let dir = create_temp_dir();
+    fs::write(dir.path().join("biome.json"), "{}").unwrap();
+    fs::write(
+        dir.path().join("import-squeeze.json"),
+        r#"{"diff": true, "vcsUseIgnoreFile": true}"#,
+    )
+    .unwrap();
+    fs::write(dir.path().join(".gitignore"), "dist/\n").unwrap();
+    fs::create_dir_all(dir.path().join("dist")).unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::write(dir.path().join("dist/out.ts"), source).unwrap();
+    fs::write(dir.path().join("index.ts"), source).unwrap();
+
+    let output = run_with_stdin(dir.path(), &["--stdin"], source);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("@@"));
+    let output = run_with_stdin(dir.path(), &["--stdin", "--no-diff"], source);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "import { a } from 'a'\nimport { b } from 'b'\n"
+    );
+
+    let checked = |args: &[&str]| {
+        let args = [&["--check", "--reporter", "json"], args].concat();
+        let output = run(dir.path(), &args);
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        report["files"].as_array().unwrap().len()
+    };
+    assert_eq!(checked(&[]), 1);
+    assert_eq!(checked(&["--no-vcs-use-ignore-file"]), 2);
+    // The last of a flag and its `--no-` form wins.
+    assert_eq!(
+        checked(&["--no-vcs-use-ignore-file", "--vcs-use-ignore-file"]),
+        1
+    );
+}
+
+#[test]
+fn test_staged_respects_config_and_settings_excludes() {
+    let dir = create_temp_dir();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(
+        dir.path().join("biome.json"),
+        r#"{"files": {"includes": ["**", "!dist"]}}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("import-squeeze.json"),
+        r#"{"excludes": ["**/*.gen.ts"]}"#,
+    )
+    .unwrap();
+    let source = "import { a } from 'a'\n\nimport { b } from 'b'\n";
+    fs::create_dir_all(dir.path().join("dist")).unwrap();
+    for name in ["index.ts", "types.gen.ts", "dist/out.ts"] {
+        fs::write(dir.path().join(name), source).unwrap();
+    }
+    git(dir.path(), &["add", "."]);
+
+    let output = run(dir.path(), &["--staged", "--check", "--reporter", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let files = report["files"].as_array().unwrap();
+    assert_eq!(files.len(), 1);
+    assert!(files[0]["path"].as_str().unwrap().ends_with("index.ts"));
+}